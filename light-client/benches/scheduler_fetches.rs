@@ -0,0 +1,105 @@
+//! Compare the number of light blocks fetched during bisection by the basic
+//! bisecting scheduler and by the store-aware production scheduler,
+//! on the JSON bisection fixtures.
+//!
+//! Run with `cargo bench --bench scheduler_fetches`.
+
+use std::convert::TryInto;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tendermint_light_client::{
+    components::{
+        io::{AtHeight, Io},
+        scheduler::{self, Scheduler},
+        verifier::ProdVerifier,
+    },
+    light_client::{LightClient, Options, VerificationMode},
+    state::State,
+    store::{memory::MemoryStore, LightStore, VerifiedStatus},
+    tests::*,
+    types::LightBlock,
+};
+
+const FIXTURES_DIRS: &[&str] = &[
+    "./tests/support/bisection/single_peer",
+    "./tests/support/bisection/multi_peer",
+];
+
+fn read_bisection_test_case(path: &PathBuf) -> TestBisection<LightBlock> {
+    let content = fs::read_to_string(path).unwrap();
+    let tc: TestBisection<AnonLightBlock> = serde_json::from_str(&content).unwrap();
+    tc.into()
+}
+
+/// Verify the target height of the given test case against its primary with the
+/// given scheduler, and return the number of light blocks fetched along the way.
+fn count_fetches(tc: TestBisection<LightBlock>, scheduler: impl Scheduler + 'static) -> usize {
+    let primary = default_peer_id();
+
+    let options = Options {
+        trust_threshold: tc.trust_options.trust_level,
+        trusting_period: tc.trust_options.period.into(),
+        clock_drift: Duration::from_secs(10),
+        now: tc.now,
+        verification_mode: VerificationMode::Skipping,
+    };
+
+    let io = MockIo::new(tc.primary.chain_id, tc.primary.lite_blocks);
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let trusted_state = io
+        .fetch_light_block(primary, AtHeight::At(trusted_height))
+        .expect("could not 'request' light block");
+
+    let mut light_store = MemoryStore::new();
+    light_store
+        .insert(trusted_state, VerifiedStatus::Verified)
+        .unwrap();
+    let mut state = State::new(light_store);
+
+    let light_client = LightClient::new(
+        primary,
+        options,
+        MockClock { now: tc.now },
+        scheduler,
+        ProdVerifier::default(),
+        io.clone(),
+    );
+
+    let fetches_before = io.fetch_count();
+    let target_height = tc.height_to_verify.try_into().unwrap();
+    let _ = light_client.verify_to_target(target_height, &mut state);
+
+    io.fetch_count() - fetches_before
+}
+
+fn main() {
+    let (mut total_basic, mut total_prod) = (0, 0);
+
+    println!("{:<70} {:>8} {:>8}", "fixture", "basic", "prod");
+
+    for dir in FIXTURES_DIRS {
+        let mut paths = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+
+        paths.sort();
+
+        for path in paths {
+            let tc = read_bisection_test_case(&path);
+
+            let basic = count_fetches(tc.clone(), scheduler::basic_bisecting_schedule);
+            let prod = count_fetches(tc, scheduler::ProdScheduler::new());
+
+            total_basic += basic;
+            total_prod += prod;
+
+            println!("{:<70} {:>8} {:>8}", path.display(), basic, prod);
+        }
+    }
+
+    println!("{:<70} {:>8} {:>8}", "total", total_basic, total_prod);
+}
@@ -0,0 +1,78 @@
+//! Measure the throughput of `verify_signatures` on the signatures of large synthetic
+//! validator sets, which it verifies one by one as consensus does.
+//!
+//! Run with `cargo bench --bench signature_verification`.
+
+use std::time::{Duration, Instant};
+
+use ed25519_dalek::Keypair;
+use rand::rngs::OsRng;
+
+use tendermint::{validator, vote, PublicKey};
+use tendermint_light_client::operations::signatures::{verify_signatures, SignatureCheck};
+
+const VALIDATOR_SET_SIZES: &[usize] = &[10, 100, 150, 300];
+const ROUNDS: u32 = 20;
+
+struct SignedMessage {
+    validator: validator::Info,
+    sign_bytes: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+/// Generate a validator set of the given size, along with a signed message from each validator.
+fn signed_messages(size: usize) -> Vec<SignedMessage> {
+    let mut csprng = OsRng;
+
+    (0..size)
+        .map(|i| {
+            let keypair = Keypair::generate(&mut csprng);
+            let sign_bytes = format!("precommit from validator #{}", i).into_bytes();
+            let signature = keypair.sign(&sign_bytes).to_bytes().to_vec();
+
+            let pub_key = PublicKey::from_raw_ed25519(keypair.public.as_bytes()).unwrap();
+            let validator = validator::Info::new(pub_key, vote::Power::new(1));
+
+            SignedMessage {
+                validator,
+                sign_bytes,
+                signature,
+            }
+        })
+        .collect()
+}
+
+fn checks(messages: &[SignedMessage]) -> Vec<SignatureCheck<'_>> {
+    messages
+        .iter()
+        .map(|message| SignatureCheck {
+            validator: message.validator.clone(),
+            sign_bytes: message.sign_bytes.clone(),
+            signature: &message.signature,
+        })
+        .collect()
+}
+
+fn time(f: impl Fn() -> bool) -> Duration {
+    let started_at = Instant::now();
+
+    for _ in 0..ROUNDS {
+        assert!(f());
+    }
+
+    started_at.elapsed()
+}
+
+fn main() {
+    println!("{:>10} {:>10}", "validators", "sig/s");
+
+    for &size in VALIDATOR_SET_SIZES {
+        let messages = signed_messages(size);
+        let checks = checks(&messages);
+
+        let elapsed = time(|| verify_signatures(&checks).is_ok());
+        let signatures = (size as u32 * ROUNDS) as f64;
+
+        println!("{:>10} {:>10.0}", size, signatures / elapsed.as_secs_f64());
+    }
+}
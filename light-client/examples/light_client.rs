@@ -1,17 +1,23 @@
 use tendermint_light_client::{
+    auto_sync::{run_auto_sync, AutoSyncConfig},
     components::{
         clock::SystemClock,
         io::{AtHeight, Io, ProdIo},
         scheduler,
         verifier::ProdVerifier,
     },
+    evidence::ProdEvidenceReporter,
     fork_detector::ProdForkDetector,
-    light_client::{self, LightClient},
+    light_client::{self, LightClient, VerificationMode},
     peer_list::PeerList,
     state::State,
-    store::{sled::SledStore, LightStore, VerifiedStatus},
-    supervisor::{Instance, Supervisor},
-    types::{Height, PeerId, Time, TrustThreshold},
+    store::{
+        export::{self, LightStoreDump},
+        sled::SledStore,
+        LightStore, VerifiedStatus,
+    },
+    supervisor::{Event, Instance, Supervisor},
+    types::{ChainId, Height, PeerId, Time, TrustThreshold},
 };
 
 use gumdrop::Options;
@@ -37,6 +43,10 @@ struct CliOptions {
 enum Command {
     #[options(help = "run the light client and continuously sync up to the latest block")]
     Sync(SyncOpts),
+    #[options(help = "export the content of a light store to a JSON file")]
+    Export(ExportOpts),
+    #[options(help = "import the content of a JSON file into a light store")]
+    Import(ImportOpts),
 }
 
 #[derive(Debug, Options)]
@@ -49,6 +59,8 @@ struct SyncOpts {
         default = "tcp://127.0.0.1:26657"
     )]
     address: tendermint::net::Address,
+    #[options(help = "identifier of the chain", meta = "CHAIN_ID", required)]
+    chain_id: ChainId,
     #[options(
         help = "height of the initial trusted state (optional if store already initialized)",
         meta = "HEIGHT"
@@ -60,6 +72,32 @@ struct SyncOpts {
         default = "./lightstore"
     )]
     db_path: PathBuf,
+    #[options(help = "verify every header in order instead of skipping headers")]
+    sequential: bool,
+}
+
+#[derive(Debug, Options)]
+struct ExportOpts {
+    #[options(help = "show help for this command")]
+    help: bool,
+    #[options(help = "path to the database folder of the light store", meta = "PATH")]
+    db_path: PathBuf,
+    #[options(help = "identifier of the chain", meta = "CHAIN_ID", required)]
+    chain_id: ChainId,
+    #[options(help = "path to the JSON file to write", meta = "FILE")]
+    output: PathBuf,
+}
+
+#[derive(Debug, Options)]
+struct ImportOpts {
+    #[options(help = "show help for this command")]
+    help: bool,
+    #[options(help = "path to the database folder of the light store", meta = "PATH")]
+    db_path: PathBuf,
+    #[options(help = "identifier of the chain", meta = "CHAIN_ID", required)]
+    chain_id: ChainId,
+    #[options(help = "path to the JSON file to read", meta = "FILE")]
+    input: PathBuf,
 }
 
 fn main() {
@@ -72,27 +110,38 @@ fn main() {
             std::process::exit(1);
         }
         Some(Command::Sync(sync_opts)) => sync_cmd(sync_opts),
+        Some(Command::Export(export_opts)) => export_cmd(export_opts),
+        Some(Command::Import(import_opts)) => import_cmd(import_opts),
     }
 }
 
+fn open_db(db_path: impl AsRef<Path>) -> sled::Db {
+    sled::open(db_path).unwrap_or_else(|e| {
+        println!("[ error ] could not open database: {}", e);
+        std::process::exit(1);
+    })
+}
+
+fn open_store(db: sled::Db, chain_id: ChainId) -> SledStore {
+    SledStore::open(db, chain_id).unwrap_or_else(|e| {
+        println!("[ error ] could not upgrade database: {}", e);
+        std::process::exit(1);
+    })
+}
+
 fn make_instance(
     peer_id: PeerId,
     addr: tendermint::net::Address,
-    db_path: impl AsRef<Path>,
+    db: sled::Db,
     opts: &SyncOpts,
 ) -> Instance {
     let mut peer_map = HashMap::new();
     peer_map.insert(peer_id, addr);
 
     let timeout = Duration::from_secs(10);
-    let io = ProdIo::new(peer_map, Some(timeout));
+    let io = ProdIo::new(opts.chain_id, peer_map, Some(timeout));
 
-    let db = sled::open(db_path).unwrap_or_else(|e| {
-        println!("[ error ] could not open database: {}", e);
-        std::process::exit(1);
-    });
-
-    let mut light_store = SledStore::new(db);
+    let mut light_store = open_store(db, opts.chain_id);
 
     if let Some(height) = opts.trusted_height {
         let trusted_state = io
@@ -102,7 +151,12 @@ fn make_instance(
                 std::process::exit(1);
             });
 
-        light_store.insert(trusted_state, VerifiedStatus::Verified);
+        light_store
+            .insert(trusted_state, VerifiedStatus::Verified)
+            .unwrap_or_else(|e| {
+                println!("[ error ] could not store trusted header: {}", e);
+                std::process::exit(1);
+            });
     } else {
         if light_store.highest(VerifiedStatus::Verified).is_none() {
             println!("[ error ] no trusted state in database, please specify a trusted header");
@@ -123,6 +177,11 @@ fn make_instance(
         trusting_period: Duration::from_secs(36000),
         clock_drift: Duration::from_secs(1),
         now: Time::now(),
+        verification_mode: if opts.sequential {
+            VerificationMode::Sequential
+        } else {
+            VerificationMode::Skipping
+        },
     };
 
     let verifier = ProdVerifier::default();
@@ -143,29 +202,88 @@ fn sync_cmd(opts: SyncOpts) {
     let primary_path = opts.db_path.clone().join(primary.to_string());
     let witness_path = opts.db_path.clone().join(witness.to_string());
 
-    let primary_instance = make_instance(primary, addr.clone(), primary_path, &opts);
-    let witness_instance = make_instance(witness, addr.clone(), witness_path, &opts);
+    let primary_db = open_db(primary_path);
+    let witness_db = open_db(witness_path);
+
+    let primary_instance = make_instance(primary, addr.clone(), primary_db.clone(), &opts);
+    let witness_instance = make_instance(witness, addr.clone(), witness_db, &opts);
 
     let peer_list = PeerList::builder()
         .primary(primary, primary_instance)
         .witness(witness, witness_instance)
         .build();
 
-    let mut supervisor = Supervisor::new(peer_list, ProdForkDetector::default());
-    let mut handle = supervisor.handle();
+    let mut peer_map = HashMap::new();
+    peer_map.insert(primary, addr.clone());
+    peer_map.insert(witness, addr);
+
+    let evidence_reporter = ProdEvidenceReporter::new(peer_map, Some(Duration::from_secs(10)));
+
+    let mut supervisor = Supervisor::new(peer_list, ProdForkDetector::default(), evidence_reporter);
+
+    // The state of the supervisor is kept alongside the light store of the primary,
+    // under its own prefix.
+    if let Err(e) = supervisor.restore_state(&primary_db) {
+        println!("[ error ] could not restore supervisor state: {}", e);
+        std::process::exit(1);
+    }
+
+    supervisor.persist_state(primary_db);
+
+    let events = supervisor.handle().subscribe();
+    let sync_handle = supervisor.async_handle();
 
     std::thread::spawn(|| supervisor.run());
 
-    loop {
-        handle.verify_to_highest_async(|result| match result {
-            Ok(light_block) => {
+    let config = AutoSyncConfig::interval(Duration::from_millis(800));
+    std::thread::spawn(|| run_auto_sync(sync_handle, config));
+
+    for event in futures::executor::block_on_stream(events) {
+        match event {
+            Event::NewTrustedBlock(light_block) => {
                 println!("[ info  ] synced to block {}", light_block.height());
             }
-            Err(e) => {
+            Event::VerificationFailed(e) => {
                 println!("[ error ] sync failed: {}", e);
             }
+            _ => (),
+        }
+    }
+}
+
+fn export_cmd(opts: ExportOpts) {
+    let light_store = open_store(open_db(&opts.db_path), opts.chain_id);
+    let dump = export::export(&light_store);
+
+    let json = serde_json::to_string_pretty(&dump).unwrap();
+    std::fs::write(&opts.output, json).unwrap_or_else(|e| {
+        println!("[ error ] could not write export: {}", e);
+        std::process::exit(1);
+    });
+
+    println!(
+        "[ info  ] exported {} light blocks to {}",
+        dump.light_blocks.len(),
+        opts.output.display()
+    );
+}
+
+fn import_cmd(opts: ImportOpts) {
+    let dump: LightStoreDump = std::fs::read_to_string(&opts.input)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            println!("[ error ] could not read export: {}", e);
+            std::process::exit(1);
         });
 
-        std::thread::sleep(Duration::from_millis(800));
+    let mut light_store = open_store(open_db(&opts.db_path), opts.chain_id);
+
+    match export::import(&mut light_store, dump) {
+        Ok(imported) => println!("[ info  ] imported {} light blocks", imported),
+        Err(e) => {
+            println!("[ error ] could not import light blocks: {}", e);
+            std::process::exit(1);
+        }
     }
 }
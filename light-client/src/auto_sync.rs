@@ -0,0 +1,218 @@
+//! Background synchronization of a `Supervisor` with the head of its primary.
+//!
+//! Instead of calling `verify_to_highest` in a loop, one can spawn the `auto_sync` task
+//! alongside the supervisor, and subscribe to the `Event::NewTrustedBlock` events
+//! of the supervisor to be notified of each newly trusted light block.
+//! The outcome of the synchronization attempts is recorded in the `AutoSyncStatus`
+//! of the configuration.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tendermint::net;
+use tendermint_rpc::event_listener::{EventListener, EventSubscription, TMEventData};
+use thiserror::Error;
+
+use crate::{components::io::Runtime, errors::Error, supervisor::AsyncHandle};
+
+/// What triggers a new synchronization with the head of the primary.
+#[derive(Clone, Debug)]
+pub enum Trigger {
+    /// Synchronize on a fixed interval
+    Interval(Duration),
+    /// Synchronize whenever a new block is announced by the node at the given
+    /// address, via its websocket endpoint
+    NewBlock(net::Address),
+}
+
+/// Configuration of the background synchronization.
+#[derive(Clone, Debug)]
+pub struct AutoSyncConfig {
+    /// What triggers a new synchronization
+    pub trigger: Trigger,
+    /// How long to wait before retrying after a first failure
+    pub initial_backoff: Duration,
+    /// How long to wait at most before retrying after consecutive failures
+    pub max_backoff: Duration,
+    /// Where the outcome of the synchronization attempts is recorded,
+    /// shared by the clones of this configuration
+    pub status: AutoSyncStatus,
+}
+
+impl AutoSyncConfig {
+    /// Synchronize on the given interval, with the default backoff.
+    pub fn interval(interval: Duration) -> Self {
+        Self::new(Trigger::Interval(interval))
+    }
+
+    /// Synchronize on each new block announced by the node at the given address,
+    /// with the default backoff.
+    pub fn new_block(address: net::Address) -> Self {
+        Self::new(Trigger::NewBlock(address))
+    }
+
+    fn new(trigger: Trigger) -> Self {
+        Self {
+            trigger,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            status: AutoSyncStatus::default(),
+        }
+    }
+}
+
+/// Why a synchronization attempt failed.
+#[derive(Debug, Error)]
+pub enum AutoSyncError {
+    /// The supervisor failed to verify the head of the primary
+    #[error("verification failed: {0}")]
+    Verification(#[source] Error),
+    /// The next block could not be awaited
+    #[error("could not wait for the next block: {0}")]
+    NewBlock(#[source] tendermint_rpc::Error),
+}
+
+/// The outcome of the latest synchronization attempts.
+///
+/// The clones of a status share the same outcome, so that it can be
+/// queried while the synchronization is running.
+#[derive(Clone, Debug, Default)]
+pub struct AutoSyncStatus(Arc<Mutex<Outcome>>);
+
+#[derive(Debug, Default)]
+struct Outcome {
+    last_error: Option<Arc<AutoSyncError>>,
+    consecutive_failures: u32,
+}
+
+impl AutoSyncStatus {
+    /// The error of the latest failed attempt, if any, even if later attempts succeeded.
+    pub fn last_error(&self) -> Option<Arc<AutoSyncError>> {
+        self.0.lock().unwrap().last_error.clone()
+    }
+
+    /// Number of attempts which failed since the latest successful one.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.0.lock().unwrap().consecutive_failures
+    }
+
+    fn record_success(&self) {
+        self.0.lock().unwrap().consecutive_failures = 0;
+    }
+
+    fn record_failure(&self, e: AutoSyncError) {
+        let mut outcome = self.0.lock().unwrap();
+        outcome.last_error = Some(Arc::new(e));
+        outcome.consecutive_failures += 1;
+    }
+}
+
+/// Exponential backoff between retries, reset on success.
+struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    /// The amount of time to wait before the next retry.
+    fn next(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = std::cmp::min(self.current * 2, self.max);
+        delay
+    }
+
+    fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+/// Keep the supervisor behind the given handle in sync with the head of its primary,
+/// until the supervisor terminates.
+///
+/// Each newly trusted light block is emitted by the supervisor to its subscribers
+/// as an `Event::NewTrustedBlock`. After a failed synchronization, which is recorded
+/// in `config.status`, the next attempt is delayed by an exponential backoff,
+/// regardless of the trigger.
+pub async fn auto_sync(mut handle: AsyncHandle, config: AutoSyncConfig) {
+    let mut backoff = Backoff::new(config.initial_backoff, config.max_backoff);
+    let mut listener = None;
+
+    loop {
+        match handle.try_verify_to_highest().await {
+            // The supervisor has terminated
+            None => return,
+            Some(Ok(_)) => {
+                config.status.record_success();
+                backoff.reset();
+            }
+            Some(Err(e)) => {
+                config.status.record_failure(AutoSyncError::Verification(e));
+                tokio::time::delay_for(backoff.next()).await;
+                continue;
+            }
+        }
+
+        match &config.trigger {
+            Trigger::Interval(interval) => tokio::time::delay_for(*interval).await,
+            Trigger::NewBlock(address) => {
+                if let Err(e) = next_block(&mut listener, address).await {
+                    config.status.record_failure(AutoSyncError::NewBlock(e));
+                    tokio::time::delay_for(backoff.next()).await;
+                }
+            }
+        }
+    }
+}
+
+/// Blocking version of `auto_sync`.
+///
+/// This function should typically be called within a new thread with `std::thread::spawn`.
+pub fn run_auto_sync(handle: AsyncHandle, config: AutoSyncConfig) {
+    Runtime::default().block_on(auto_sync(handle, config))
+}
+
+/// Wait for the next block announced by the node at the given address,
+/// connecting to it first if needed.
+async fn next_block(
+    listener: &mut Option<EventListener>,
+    address: &net::Address,
+) -> Result<(), tendermint_rpc::Error> {
+    if listener.is_none() {
+        let mut new_listener = EventListener::connect(address.clone()).await?;
+
+        new_listener
+            .subscribe(EventSubscription::BlockSubscription)
+            .await
+            .map_err(|e| tendermint_rpc::Error::websocket_error(e.to_string()))?;
+
+        *listener = Some(new_listener);
+    }
+
+    loop {
+        // SAFETY: The listener has been set above.
+        let event = listener.as_mut().unwrap().get_event().await;
+
+        match event {
+            Ok(Some(event)) => {
+                if let TMEventData::EventDataNewBlock(_) = event.data {
+                    return Ok(());
+                }
+            }
+            Ok(None) => continue,
+            Err(e) => {
+                // Reconnect on the next call
+                *listener = None;
+                return Err(e);
+            }
+        }
+    }
+}
@@ -1,16 +1,175 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
 use crate::types::Time;
 
 /// Abstracts over the current time.
-pub trait Clock: Send {
+pub trait Clock: Send + Sync {
     /// Get the current time.
     fn now(&self) -> Time;
+
+    /// Update the estimated skew of this clock with the given times of the latest
+    /// headers of several peers.
+    ///
+    /// Returns the new estimate, if any.
+    /// Clocks which do not estimate their skew ignore the given times.
+    fn record_header_times(&self, _header_times: &[Time]) -> Option<Skew> {
+        None
+    }
+
+    /// Whether the estimated skew of this clock exceeds what is tolerated.
+    ///
+    /// Clocks which do not estimate their skew are never deemed skewed.
+    fn is_skewed(&self) -> bool {
+        false
+    }
+
+    /// The estimated skew of this clock, if it is so large that verification
+    /// should be refused until the clock is fixed.
+    ///
+    /// Clocks which do not estimate their skew never refuse verification.
+    fn excessive_skew(&self) -> Option<Skew> {
+        None
+    }
 }
 
 /// Provides the current wall clock time.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct SystemClock;
 impl Clock for SystemClock {
     fn now(&self) -> Time {
         Time::now()
     }
 }
+
+/// Offset of the local clock relative to the time of the latest headers of the peers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Skew {
+    /// The local clock is ahead of the latest headers by the given duration
+    Ahead(Duration),
+    /// The local clock is behind the latest headers by the given duration
+    Behind(Duration),
+}
+
+impl Skew {
+    /// Estimate the skew of the given local time relative to the given times of
+    /// the latest headers of several peers, by comparing it to their median.
+    ///
+    /// Returns `None` if no header time is given.
+    pub fn estimate(now: Time, header_times: &[Time]) -> Option<Self> {
+        let mut header_times = header_times.to_vec();
+        header_times.sort();
+
+        let median = *header_times.get(header_times.len() / 2)?;
+
+        match now.duration_since(median) {
+            Ok(ahead) => Some(Self::Ahead(ahead)),
+            Err(_) => Some(Self::Behind(median.duration_since(now).unwrap_or_default())),
+        }
+    }
+
+    /// Whether this skew exceeds the given clock drift, given that the latest headers
+    /// may lag behind the actual time by up to `max_lag`.
+    ///
+    /// A local clock behind the headers by more than the clock drift would reject the
+    /// latest headers as coming from the future. As the latest header of a live chain
+    /// lags behind the actual time by up to a block interval, a local clock ahead of the
+    /// headers is expected, but only by up to `max_lag` on top of the clock drift.
+    /// Further ahead, it would deem trusted states expired too early.
+    pub fn exceeds(&self, clock_drift: Duration, max_lag: Duration) -> bool {
+        match self {
+            Self::Ahead(ahead) => *ahead > clock_drift + max_lag,
+            Self::Behind(behind) => *behind > clock_drift,
+        }
+    }
+}
+
+/// How far behind the actual time the latest headers of the peers may lag by default,
+/// ie. a generous upper bound on the block interval of a live chain.
+pub const DEFAULT_MAX_HEADER_LAG: Duration = Duration::from_secs(10 * 60);
+
+/// A clock which estimates its own skew by comparing the local time with the time
+/// of the latest headers served by several peers.
+///
+/// The estimation is not performed by the clock itself, but must be fed with the times
+/// of the latest headers via `Clock::record_header_times`, eg. by `Supervisor::detect_clock_skew`.
+/// The clones of a clock share their estimate, so that the same clock can be
+/// given to the light clients of all the instances of a supervisor.
+///
+/// The clock is deemed skewed if its estimated skew exceeds the clock drift, allowing the
+/// latest headers to lag behind by up to `DEFAULT_MAX_HEADER_LAG` unless set otherwise.
+/// If set to refuse verification, the clock reports its skew via `Clock::excessive_skew`
+/// as long as it is skewed, and the light clients using it then fail
+/// with `ErrorKind::ClockSkewed`.
+#[derive(Clone, Debug)]
+pub struct DriftDetectingClock<C = SystemClock> {
+    inner: C,
+    clock_drift: Duration,
+    max_lag: Duration,
+    refuse_when_skewed: bool,
+    skew: Arc<RwLock<Option<Skew>>>,
+}
+
+impl<C: Clock> DriftDetectingClock<C> {
+    /// Wrap the given clock, and deem it skewed if its estimated skew exceeds
+    /// the given clock drift, typically the one of the light client `Options`.
+    pub fn new(inner: C, clock_drift: Duration) -> Self {
+        Self {
+            inner,
+            clock_drift,
+            max_lag: DEFAULT_MAX_HEADER_LAG,
+            refuse_when_skewed: false,
+            skew: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Whether verification should be refused while the clock is skewed.
+    pub fn refuse_when_skewed(self, refuse_when_skewed: bool) -> Self {
+        Self {
+            refuse_when_skewed,
+            ..self
+        }
+    }
+
+    /// How far behind the actual time the latest headers of the peers may lag,
+    /// eg. a few block intervals of the chain. See `Skew::exceeds`.
+    pub fn max_lag(self, max_lag: Duration) -> Self {
+        Self { max_lag, ..self }
+    }
+
+    /// The latest estimated skew of this clock, if any.
+    pub fn skew(&self) -> Option<Skew> {
+        *self.skew.read().unwrap()
+    }
+}
+
+impl<C: Clock> Clock for DriftDetectingClock<C> {
+    fn now(&self) -> Time {
+        self.inner.now()
+    }
+
+    /// Returns the new estimate, or `None` if no header time is given,
+    /// in which case the previous estimate is kept.
+    fn record_header_times(&self, header_times: &[Time]) -> Option<Skew> {
+        let skew = Skew::estimate(self.inner.now(), header_times)?;
+        *self.skew.write().unwrap() = Some(skew);
+        Some(skew)
+    }
+
+    /// Whether the latest estimated skew of this clock exceeds the clock drift,
+    /// given the maximum lag of the headers.
+    fn is_skewed(&self) -> bool {
+        self.skew()
+            .map_or(false, |skew| skew.exceeds(self.clock_drift, self.max_lag))
+    }
+
+    fn excessive_skew(&self) -> Option<Skew> {
+        if self.refuse_when_skewed && self.is_skewed() {
+            self.skew()
+        } else {
+            None
+        }
+    }
+}
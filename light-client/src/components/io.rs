@@ -1,21 +1,27 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use contracts::{contract_trait, post, pre};
+use async_trait::async_trait;
+use contracts::{contract_trait, post};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use tendermint::{
-    block::signed_header::SignedHeader as TMSignedHeader, validator::Set as TMValidatorSet,
-};
-
+use tendermint::lite::ValidatorSet as _;
 use tendermint_rpc as rpc;
 
 use crate::{
     bail,
-    types::{Height, LightBlock, PeerId},
+    types::{ChainId, Hash, Height, LightBlock, PeerId, SignedHeader, ValidatorSet},
 };
 
+pub mod retry;
+use retry::RetryPolicy;
+
+mod validation;
+
 pub enum AtHeight {
     At(Height),
     Highest,
@@ -33,9 +39,18 @@ impl From<Height> for AtHeight {
 
 #[derive(Clone, Debug, Error, PartialEq, Serialize, Deserialize)]
 pub enum IoError {
-    /// Wrapper for a `tendermint::rpc::Error`.
+    /// Wrapper for a `tendermint::rpc::Error` returned by the full node.
     #[error(transparent)]
-    IoError(#[from] rpc::Error),
+    IoError(rpc::Error),
+
+    /// The full node could not be reached, or did not answer with a JSONRPC response.
+    #[error("transport error: {0}")]
+    Transport(rpc::Error),
+
+    /// The full node served a response which is inconsistent with the request,
+    /// or with itself.
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
 
     /// Given height is invalid
     #[error("invalid height: {0}")]
@@ -46,19 +61,75 @@ pub enum IoError {
     Timeout(PeerId),
 }
 
+impl IoError {
+    /// Whether this error is likely to be transient, in which case the request may be retried.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Transport(_) | Self::Timeout(_) => true,
+            Self::IoError(_) | Self::InvalidResponse(_) | Self::InvalidHeight(_) => false,
+        }
+    }
+}
+
+impl From<rpc::Error> for IoError {
+    fn from(e: rpc::Error) -> Self {
+        if e.code() == rpc::error::Code::HttpError {
+            Self::Transport(e)
+        } else {
+            Self::IoError(e)
+        }
+    }
+}
+
 /// Interface for fetching light blocks from a full node, typically via the RPC client.
 #[contract_trait]
-pub trait Io: Send {
+pub trait Io: Send + Sync {
     /// Fetch a light block at the given height from the peer with the given peer ID.
     ///
     /// ## Postcondition
     /// - The provider of the returned light block matches the given peer [LCV-IO-POST-PROVIDER]
     #[post(ret.as_ref().map(|lb| lb.provider == peer).unwrap_or(true))]
     fn fetch_light_block(&self, peer: PeerId, height: AtHeight) -> Result<LightBlock, IoError>;
+
+    /// Fetch a light block at the given height from the peer with the given peer ID,
+    /// re-using the given validator sets instead of downloading the ones with the same hash.
+    ///
+    /// By default, the known validator sets are ignored.
+    fn fetch_light_block_with_validators(
+        &self,
+        peer: PeerId,
+        height: AtHeight,
+        _known_validators: &[ValidatorSet],
+    ) -> Result<LightBlock, IoError> {
+        self.fetch_light_block(peer, height)
+    }
+
+    /// Fetch only the signed header at the given height from the peer with the given peer ID.
+    ///
+    /// By default, the whole light block is fetched.
+    fn fetch_signed_header(&self, peer: PeerId, height: AtHeight) -> Result<SignedHeader, IoError> {
+        self.fetch_light_block(peer, height)
+            .map(|light_block| light_block.signed_header)
+    }
+
+    /// Fetch only the validator set at the given height from the peer with the given peer ID.
+    ///
+    /// By default, the whole light block is fetched.
+    fn fetch_validator_set(&self, peer: PeerId, height: Height) -> Result<ValidatorSet, IoError> {
+        self.fetch_light_block(peer, AtHeight::At(height))
+            .map(|light_block| light_block.validators)
+    }
+
+    /// Fetch the light block whose header has the given hash from the peer with the given peer ID.
+    ///
+    /// By default, fetching a light block by hash is not supported.
+    fn fetch_by_hash(&self, _peer: PeerId, _hash: Hash) -> Result<LightBlock, IoError> {
+        Err(rpc::Error::method_not_found("block_by_hash").into())
+    }
 }
 
 #[contract_trait]
-impl<F: Send> Io for F
+impl<F: Send + Sync> Io for F
 where
     F: Fn(PeerId, AtHeight) -> Result<LightBlock, IoError>,
 {
@@ -67,113 +138,445 @@ where
     }
 }
 
+/// Asynchronous interface for fetching light blocks from a full node,
+/// typically via the RPC client.
+///
+/// Unlike `Io`, implementations of this trait are expected not to block
+/// the current thread, so that they can run within an existing runtime.
+///
+/// ## Postcondition
+/// - The provider of the returned light block matches the given peer [LCV-IO-POST-PROVIDER]
+#[async_trait]
+pub trait AsyncIo: Send + Sync {
+    /// Fetch a light block at the given height from the peer with the given peer ID.
+    async fn fetch_light_block(
+        &self,
+        peer: PeerId,
+        height: AtHeight,
+    ) -> Result<LightBlock, IoError>;
+
+    /// Async version of `Io::fetch_light_block_with_validators`.
+    async fn fetch_light_block_with_validators(
+        &self,
+        peer: PeerId,
+        height: AtHeight,
+        _known_validators: &[ValidatorSet],
+    ) -> Result<LightBlock, IoError> {
+        self.fetch_light_block(peer, height).await
+    }
+
+    /// Async version of `Io::fetch_signed_header`.
+    async fn fetch_signed_header(
+        &self,
+        peer: PeerId,
+        height: AtHeight,
+    ) -> Result<SignedHeader, IoError> {
+        self.fetch_light_block(peer, height)
+            .await
+            .map(|light_block| light_block.signed_header)
+    }
+
+    /// Async version of `Io::fetch_validator_set`.
+    async fn fetch_validator_set(
+        &self,
+        peer: PeerId,
+        height: Height,
+    ) -> Result<ValidatorSet, IoError> {
+        self.fetch_light_block(peer, AtHeight::At(height))
+            .await
+            .map(|light_block| light_block.validators)
+    }
+
+    /// Async version of `Io::fetch_by_hash`.
+    async fn fetch_by_hash(&self, _peer: PeerId, _hash: Hash) -> Result<LightBlock, IoError> {
+        Err(rpc::Error::method_not_found("block_by_hash").into())
+    }
+}
+
+/// Fetch the light block at the given height from the given `AsyncIo` component,
+/// by fetching its signed header, and then only the validator sets whose hash
+/// in the header does not match the hash of one of the known validator sets.
+pub async fn fetch_light_block_reusing<T: AsyncIo + ?Sized>(
+    io: &T,
+    peer: PeerId,
+    height: AtHeight,
+    known_validators: &[ValidatorSet],
+) -> Result<LightBlock, IoError> {
+    let signed_header = io.fetch_signed_header(peer, height).await?;
+    let height: Height = signed_header.header.height.into();
+
+    let known = |hash: Hash| {
+        known_validators
+            .iter()
+            .find(|validators| validators.hash() == hash)
+            .cloned()
+    };
+
+    let validator_set = match known(signed_header.header.validators_hash) {
+        Some(validator_set) => validator_set,
+        None => io.fetch_validator_set(peer, height).await?,
+    };
+
+    let next_validator_set = match known(signed_header.header.next_validators_hash) {
+        Some(next_validator_set) => next_validator_set,
+        None => io.fetch_validator_set(peer, height + 1).await?,
+    };
+
+    let light_block = LightBlock::new(signed_header, validator_set, next_validator_set, peer);
+
+    Ok(light_block)
+}
+
+/// Exposes a synchronous `Io` component as an `AsyncIo` one.
+///
+/// Each request is performed as a blocking task of the current `tokio` runtime, so that
+/// requests to several peers can proceed concurrently, and so that a request which
+/// exceeds its deadline does not block the current thread.
+///
+/// ## Note
+/// The requests must therefore be awaited from within a `tokio` runtime.
+pub struct BlockingIo<T>(Arc<T>);
+
+impl<T> BlockingIo<T> {
+    /// Wrap the given synchronous `Io` component.
+    pub fn new(io: T) -> Self {
+        Self(Arc::new(io))
+    }
+}
+
+impl<T: Io + 'static> BlockingIo<T> {
+    /// Perform the given request with the wrapped `Io` component as a blocking task.
+    async fn spawn<R: Send + 'static>(
+        &self,
+        peer: PeerId,
+        request: impl FnOnce(&T) -> R + Send + 'static,
+    ) -> R {
+        let io = Arc::clone(&self.0);
+
+        tokio::task::spawn_blocking(move || request(&io))
+            .await
+            .unwrap_or_else(|_| panic!("task fetching from peer {} panicked", peer))
+    }
+}
+
+#[async_trait]
+impl<T: Io + 'static> AsyncIo for BlockingIo<T> {
+    async fn fetch_light_block(
+        &self,
+        peer: PeerId,
+        height: AtHeight,
+    ) -> Result<LightBlock, IoError> {
+        self.spawn(peer, move |io| io.fetch_light_block(peer, height))
+            .await
+    }
+
+    async fn fetch_light_block_with_validators(
+        &self,
+        peer: PeerId,
+        height: AtHeight,
+        known_validators: &[ValidatorSet],
+    ) -> Result<LightBlock, IoError> {
+        let known_validators = known_validators.to_vec();
+
+        self.spawn(peer, move |io| {
+            io.fetch_light_block_with_validators(peer, height, &known_validators)
+        })
+        .await
+    }
+
+    async fn fetch_signed_header(
+        &self,
+        peer: PeerId,
+        height: AtHeight,
+    ) -> Result<SignedHeader, IoError> {
+        self.spawn(peer, move |io| io.fetch_signed_header(peer, height))
+            .await
+    }
+
+    async fn fetch_validator_set(
+        &self,
+        peer: PeerId,
+        height: Height,
+    ) -> Result<ValidatorSet, IoError> {
+        self.spawn(peer, move |io| io.fetch_validator_set(peer, height))
+            .await
+    }
+
+    async fn fetch_by_hash(&self, peer: PeerId, hash: Hash) -> Result<LightBlock, IoError> {
+        self.spawn(peer, move |io| io.fetch_by_hash(peer, hash))
+            .await
+    }
+}
+
 /// Production implementation of the Io component, which fetches
 /// light blocks from full nodes via RPC.
+///
+/// Requests failing with a transient error are retried according to the retry policy,
+/// and the connections to each peer are kept alive and re-used across requests.
+///
+/// The responses are checked for consistency with the request, with each other,
+/// and with the configured chain, and are otherwise rejected with `IoError::InvalidResponse`.
+///
+/// The synchronous `Io` implementation runs the requests on a runtime owned by
+/// the component, and must therefore not be used from within an async context,
+/// in which case the `AsyncIo` implementation should be used instead.
 #[derive(Clone, Debug)]
 pub struct ProdIo {
-    peer_map: HashMap<PeerId, tendermint::net::Address>,
+    chain_id: ChainId,
+    rpc_clients: HashMap<PeerId, rpc::Client>,
     timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    runtime: Runtime,
 }
 
 #[contract_trait]
 impl Io for ProdIo {
     fn fetch_light_block(&self, peer: PeerId, height: AtHeight) -> Result<LightBlock, IoError> {
-        let signed_header = self.fetch_signed_header(peer, height)?;
-        let height: Height = signed_header.header.height.into();
+        self.runtime
+            .block_on(AsyncIo::fetch_light_block(self, peer, height))
+    }
 
-        let validator_set = self.fetch_validator_set(peer, height.into())?;
-        let next_validator_set = self.fetch_validator_set(peer, (height + 1).into())?;
+    fn fetch_light_block_with_validators(
+        &self,
+        peer: PeerId,
+        height: AtHeight,
+        known_validators: &[ValidatorSet],
+    ) -> Result<LightBlock, IoError> {
+        self.runtime
+            .block_on(AsyncIo::fetch_light_block_with_validators(
+                self,
+                peer,
+                height,
+                known_validators,
+            ))
+    }
+
+    fn fetch_signed_header(&self, peer: PeerId, height: AtHeight) -> Result<SignedHeader, IoError> {
+        self.runtime
+            .block_on(AsyncIo::fetch_signed_header(self, peer, height))
+    }
 
-        let light_block = LightBlock::new(signed_header, validator_set, next_validator_set, peer);
+    fn fetch_validator_set(&self, peer: PeerId, height: Height) -> Result<ValidatorSet, IoError> {
+        self.runtime
+            .block_on(AsyncIo::fetch_validator_set(self, peer, height))
+    }
 
-        Ok(light_block)
+    fn fetch_by_hash(&self, peer: PeerId, hash: Hash) -> Result<LightBlock, IoError> {
+        self.runtime
+            .block_on(AsyncIo::fetch_by_hash(self, peer, hash))
     }
 }
 
-impl ProdIo {
-    /// Constructs a new ProdIo component.
-    ///
-    /// A peer map which maps peer IDS to their network address must be supplied.
-    pub fn new(
-        peer_map: HashMap<PeerId, tendermint::net::Address>,
-        timeout: Option<Duration>,
-    ) -> Self {
-        Self { peer_map, timeout }
+#[async_trait]
+impl AsyncIo for ProdIo {
+    async fn fetch_light_block(
+        &self,
+        peer: PeerId,
+        height: AtHeight,
+    ) -> Result<LightBlock, IoError> {
+        let light_block = fetch_light_block_reusing(self, peer, height, &[]).await?;
+        validation::validate_validator_sets(&light_block)?;
+
+        Ok(light_block)
     }
 
-    #[pre(self.peer_map.contains_key(&peer))]
-    fn fetch_signed_header(
+    /// Only downloads the validator sets which are not among the known ones.
+    async fn fetch_light_block_with_validators(
         &self,
         peer: PeerId,
         height: AtHeight,
-    ) -> Result<TMSignedHeader, IoError> {
-        let rpc_client = self.rpc_client_for(peer);
+        known_validators: &[ValidatorSet],
+    ) -> Result<LightBlock, IoError> {
+        let light_block = fetch_light_block_reusing(self, peer, height, known_validators).await?;
+        validation::validate_validator_sets(&light_block)?;
 
-        let res = block_on(
-            async {
-                match height {
-                    AtHeight::Highest => rpc_client.latest_commit().await,
-                    AtHeight::At(height) => rpc_client.commit(height).await,
-                }
-            },
-            peer,
-            self.timeout,
-        )?;
-
-        match res {
-            Ok(response) => Ok(response.signed_header),
-            Err(err) => Err(IoError::IoError(err)),
-        }
+        Ok(light_block)
     }
 
-    #[pre(self.peer_map.contains_key(&peer))]
-    fn fetch_validator_set(
+    /// ## Precondition
+    /// - The given peer is part of the peer map.
+    async fn fetch_signed_header(
         &self,
         peer: PeerId,
         height: AtHeight,
-    ) -> Result<TMValidatorSet, IoError> {
-        let height = match height {
-            AtHeight::Highest => bail!(IoError::InvalidHeight(
-                "given height must be greater than 0".to_string()
-            )),
-            AtHeight::At(height) => height,
+    ) -> Result<SignedHeader, IoError> {
+        let response = match &height {
+            AtHeight::Highest => {
+                self.request(peer, |client| async move { client.latest_commit().await })
+                    .await?
+            }
+            &AtHeight::At(height) => {
+                self.request(peer, |client| async move { client.commit(height).await })
+                    .await?
+            }
         };
 
-        let res = block_on(
-            self.rpc_client_for(peer).validators(height),
-            peer,
-            self.timeout,
-        )?;
+        validation::validate_signed_header(&response.signed_header, &height, &self.chain_id)?;
+
+        Ok(response.signed_header)
+    }
 
-        match res {
-            Ok(response) => Ok(TMValidatorSet::new(response.validators)),
-            Err(err) => Err(IoError::IoError(err)),
+    /// ## Precondition
+    /// - The given peer is part of the peer map.
+    async fn fetch_validator_set(
+        &self,
+        peer: PeerId,
+        height: Height,
+    ) -> Result<ValidatorSet, IoError> {
+        if height == 0 {
+            bail!(IoError::InvalidHeight(
+                "given height must be greater than 0".to_string()
+            ));
         }
+
+        let response = self
+            .request(
+                peer,
+                |client| async move { client.validators(height).await },
+            )
+            .await?;
+
+        let response_height: Height = response.block_height.into();
+        if response_height != height {
+            bail!(IoError::InvalidResponse(format!(
+                "requested validator set at height {}, got validator set at height {}",
+                height, response_height
+            )));
+        }
+
+        Ok(ValidatorSet::new(response.validators))
+    }
+
+    /// Look up the height of the block with the given hash,
+    /// and fetch the light block at this height.
+    ///
+    /// ## Precondition
+    /// - The given peer is part of the peer map.
+    async fn fetch_by_hash(&self, peer: PeerId, hash: Hash) -> Result<LightBlock, IoError> {
+        let response = self
+            .request(
+                peer,
+                |client| async move { client.block_by_hash(hash).await },
+            )
+            .await?;
+
+        let height = response.block.header.height.into();
+
+        let light_block = AsyncIo::fetch_light_block(self, peer, AtHeight::At(height)).await?;
+        validation::validate_hash(&light_block, hash)?;
+
+        Ok(light_block)
+    }
+}
+
+impl ProdIo {
+    /// Constructs a new ProdIo component for the given chain, with the default retry policy.
+    ///
+    /// A peer map which maps peer IDS to their network address must be supplied.
+    pub fn new(
+        chain_id: ChainId,
+        peer_map: HashMap<PeerId, tendermint::net::Address>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        let rpc_clients = peer_map
+            .into_iter()
+            .map(|(peer, address)| (peer, rpc::Client::new(address)))
+            .collect();
+
+        Self {
+            chain_id,
+            rpc_clients,
+            timeout,
+            retry_policy: RetryPolicy::default(),
+            runtime: Runtime::default(),
+        }
+    }
+
+    /// Use the given policy to retry the requests which fail with a transient error.
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy,
+            ..self
+        }
+    }
+
+    /// Perform the request built by the given closure against the given peer,
+    /// retrying it according to the retry policy.
+    /// Each attempt is subject to the timeout, if any.
+    ///
+    /// ## Precondition
+    /// - The given peer is part of the peer map.
+    async fn request<T, F, Fut>(&self, peer: PeerId, request: F) -> Result<T, IoError>
+    where
+        F: Fn(rpc::Client) -> Fut + Sync,
+        Fut: Future<Output = Result<T, rpc::Error>> + Send,
+        T: Send,
+    {
+        let rpc_client = self.rpc_client_for(peer);
+        let (rpc_client, request, timeout) = (&rpc_client, &request, self.timeout);
+
+        self.retry_policy
+            .retry(move || async move {
+                with_timeout(request(rpc_client.clone()), peer, timeout)
+                    .await?
+                    .map_err(IoError::from)
+            })
+            .await
     }
 
     // FIXME: Cannot enable precondition because of "autoref lifetime" issue
-    // #[pre(self.peer_map.contains_key(&peer))]
+    // #[pre(self.rpc_clients.contains_key(&peer))]
     fn rpc_client_for(&self, peer: PeerId) -> rpc::Client {
-        let peer_addr = self.peer_map.get(&peer).unwrap().to_owned();
-        rpc::Client::new(peer_addr)
+        self.rpc_clients.get(&peer).unwrap().clone()
+    }
+}
+
+/// A runtime used to run futures to completion from synchronous code.
+///
+/// The underlying `tokio` runtime is only created on first use, so that components
+/// which are only driven asynchronously never create one, and is then re-used
+/// across calls, along with the connections it keeps alive.
+/// The clones of a runtime share the underlying `tokio` runtime.
+#[derive(Clone, Default)]
+pub(crate) struct Runtime(Arc<Mutex<Option<tokio::runtime::Runtime>>>);
+
+impl Runtime {
+    /// Run the given future to completion on this runtime.
+    ///
+    /// Concurrent calls on the same runtime run one after the other.
+    pub(crate) fn block_on<F: Future>(&self, f: F) -> F::Output {
+        let mut runtime = self.0.lock().unwrap();
+
+        let runtime = runtime.get_or_insert_with(|| {
+            tokio::runtime::Builder::new()
+                .basic_scheduler()
+                .enable_all()
+                .build()
+                .unwrap()
+        });
+
+        runtime.block_on(f)
     }
 }
 
-fn block_on<F: std::future::Future>(
+impl fmt::Debug for Runtime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Runtime").finish()
+    }
+}
+
+/// Await the given future, failing with `IoError::Timeout`
+/// if it does not complete within the given timeout, if any.
+pub(crate) async fn with_timeout<F: Future>(
     f: F,
     peer: PeerId,
     timeout: Option<Duration>,
 ) -> Result<F::Output, IoError> {
-    let mut rt = tokio::runtime::Builder::new()
-        .basic_scheduler()
-        .enable_all()
-        .build()
-        .unwrap();
-
     if let Some(timeout) = timeout {
-        rt.block_on(async { tokio::time::timeout(timeout, f).await })
+        tokio::time::timeout(timeout, f)
+            .await
             .map_err(|_| IoError::Timeout(peer))
     } else {
-        Ok(rt.block_on(f))
+        Ok(f.await)
     }
 }
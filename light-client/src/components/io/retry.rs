@@ -0,0 +1,97 @@
+//! Retrying of failed requests to full nodes, with exponential backoff and jitter.
+
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::IoError;
+
+/// Policy deciding whether and when to retry a failed request.
+///
+/// Only the errors which are likely to be transient are retried,
+/// see `IoError::is_retryable`.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: usize,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between two attempts
+    pub max_backoff: Duration,
+    /// Factor by which the delay grows after each retry
+    pub multiplier: f64,
+    /// Fraction of the delay, between 0 and 1, by which the delay is randomly
+    /// shortened, so that clients failing together do not retry together
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// A policy which never retries.
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// The delay before the given retry, with 1 being the first retry, without jitter.
+    pub fn backoff(&self, retry: usize) -> Duration {
+        let exponent = retry.saturating_sub(1) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+
+        if backoff.is_finite() && backoff < self.max_backoff.as_secs_f64() {
+            Duration::from_secs_f64(backoff)
+        } else {
+            self.max_backoff
+        }
+    }
+
+    /// The delay before the given retry, with 1 being the first retry, with jitter.
+    pub fn backoff_with_jitter(&self, retry: usize) -> Duration {
+        let backoff = self.backoff(retry);
+        let jitter = self.jitter.max(0.0).min(1.0);
+
+        if jitter == 0.0 {
+            return backoff;
+        }
+
+        let factor = rand::thread_rng().gen_range(1.0 - jitter, 1.0);
+        backoff.mul_f64(factor)
+    }
+
+    /// Perform the request built by the given closure, and retry it as long as it fails
+    /// with a retryable error, and the maximum number of attempts is not reached.
+    ///
+    /// Returns the error of the last attempt if all of them fail.
+    pub async fn retry<T, F, Fut>(&self, mut request: F) -> Result<T, IoError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, IoError>>,
+    {
+        let mut attempt = 1;
+
+        loop {
+            match request().await {
+                Err(e) if e.is_retryable() && attempt < self.max_attempts => {
+                    tokio::time::delay_for(self.backoff_with_jitter(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.5,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Consistency checks on the responses served by full nodes.
+
+use crate::{
+    bail,
+    operations::{HeaderHasher, ProdHeaderHasher},
+    types::{ChainId, Hash, Height, LightBlock, SignedHeader, ValidatorSet},
+};
+
+use super::{AtHeight, IoError};
+
+/// Check that the given signed header is for the requested height,
+/// and belongs to the given chain.
+///
+/// ## Errors
+/// - If the header or the commit is not at the requested height [IoError::InvalidResponse]
+/// - If the header belongs to another chain [IoError::InvalidResponse]
+pub(crate) fn validate_signed_header(
+    signed_header: &SignedHeader,
+    requested: &AtHeight,
+    chain_id: &ChainId,
+) -> Result<(), IoError> {
+    let header_height: Height = signed_header.header.height.into();
+    let commit_height: Height = signed_header.commit.height.into();
+
+    if let AtHeight::At(height) = *requested {
+        if header_height != height {
+            bail!(IoError::InvalidResponse(format!(
+                "requested header at height {}, got header at height {}",
+                height, header_height
+            )));
+        }
+    }
+
+    if commit_height != header_height {
+        bail!(IoError::InvalidResponse(format!(
+            "header at height {} comes with a commit at height {}",
+            header_height, commit_height
+        )));
+    }
+
+    if &signed_header.header.chain_id != chain_id {
+        bail!(IoError::InvalidResponse(format!(
+            "expected header from chain {}, got header from chain {}",
+            chain_id, signed_header.header.chain_id
+        )));
+    }
+
+    Ok(())
+}
+
+/// Check that the validator sets of the given light block match
+/// the hashes found in its header.
+///
+/// ## Errors
+/// - If either validator set does not match the header [IoError::InvalidResponse]
+pub(crate) fn validate_validator_sets(light_block: &LightBlock) -> Result<(), IoError> {
+    let header = &light_block.signed_header.header;
+
+    check_validator_set_hash(
+        &light_block.validators,
+        header.validators_hash,
+        "validator set",
+        light_block.height(),
+    )?;
+
+    check_validator_set_hash(
+        &light_block.next_validators,
+        header.next_validators_hash,
+        "next validator set",
+        light_block.height(),
+    )
+}
+
+/// Check that the given light block has the requested hash.
+///
+/// ## Errors
+/// - If the hash of the header differs from the requested one [IoError::InvalidResponse]
+pub(crate) fn validate_hash(light_block: &LightBlock, requested: Hash) -> Result<(), IoError> {
+    let hash = ProdHeaderHasher.hash(&light_block.signed_header.header);
+
+    if hash != requested {
+        bail!(IoError::InvalidResponse(format!(
+            "requested block with hash {}, got block with hash {}",
+            requested, hash
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_validator_set_hash(
+    validator_set: &ValidatorSet,
+    expected: Hash,
+    name: &str,
+    height: Height,
+) -> Result<(), IoError> {
+    let hash = validator_set.hash();
+
+    if hash != expected {
+        bail!(IoError::InvalidResponse(format!(
+            "{} hash {} does not match hash {} in header at height {}",
+            name, hash, expected, height
+        )));
+    }
+
+    Ok(())
+}
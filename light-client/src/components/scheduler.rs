@@ -1,16 +1,17 @@
 use crate::{
     store::{LightStore, VerifiedStatus},
-    types::Height,
+    types::{Height, LightBlock},
 };
 
 use contracts::*;
+use std::{collections::BTreeSet, sync::Mutex};
 
 /// The scheduler decides what block to verify next given the current and target heights.
 ///
 /// The scheduler is given access to the light store, in order to optionally
 /// improve performance by picking a next block that has already been fetched.
 #[contract_trait]
-pub trait Scheduler: Send {
+pub trait Scheduler: Send + Sync {
     /// Decides what block to verify next.
     ///
     /// ## Precondition
@@ -29,7 +30,7 @@ pub trait Scheduler: Send {
 }
 
 #[contract_trait]
-impl<F: Send + Clone> Scheduler for F
+impl<F: Send + Sync + Clone> Scheduler for F
 where
     F: Fn(&dyn LightStore, Height, Height) -> Height,
 {
@@ -74,6 +75,162 @@ pub fn basic_bisecting_schedule(
     }
 }
 
+/// Sequential scheduler which always picks the height right above the highest trusted
+/// block, so that every block up to the target height gets verified in order.
+///
+/// ## Precondition
+/// - The light store contains at least one verified block. [LCV-SCHEDULE-PRE.1]
+///
+/// ## Postcondition
+/// - The resulting height must be valid according to `valid_schedule`. [LCV-SCHEDULE-POST.1]
+#[pre(light_store.highest(VerifiedStatus::Verified).is_some())]
+#[post(valid_schedule(ret, target_height, current_height, light_store))]
+pub fn sequential_schedule(
+    light_store: &dyn LightStore,
+    current_height: Height,
+    target_height: Height,
+) -> Height {
+    let trusted_height = light_store
+        .highest(VerifiedStatus::Verified)
+        .map(|lb| lb.height())
+        .unwrap();
+
+    if trusted_height < target_height {
+        trusted_height + 1
+    } else {
+        target_height
+    }
+}
+
+/// Maximum number of changes of the validator set remembered by the `ProdScheduler`.
+pub const MAX_VALIDATOR_SET_CHANGES: usize = 1024;
+
+/// Production scheduler which minimizes the number of light blocks to fetch during
+/// bisection, by favoring heights for which a light block is already in the light store.
+///
+/// When the block at the current height cannot be verified yet, it picks, among the heights
+/// between the trusted height and the current height, the one closest to the midpoint
+/// for which either:
+/// - a light block has already been fetched but not verified yet, or
+/// - a change of the validator set is known to happen at the next height.
+///
+/// The latter are recorded from the light blocks seen in the light store, and remembered
+/// across calls, so that they survive the removal of those blocks from the light store.
+/// Verifying the last block before a change of the validator set is a good bet, as it is
+/// still signed by the validator set of the trusted state.
+///
+/// As those light blocks are not verified yet, the recorded changes only ever serve as hints
+/// for picking the next height. Only the changes between the trusted height and the target
+/// height are kept, up to `MAX_VALIDATOR_SET_CHANGES` of them, the others being pruned
+/// on each call.
+///
+/// If there is no such height, it falls back to the midpoint, as `basic_bisecting_schedule`.
+#[derive(Debug, Default)]
+pub struct ProdScheduler {
+    validator_set_changes: Mutex<BTreeSet<Height>>,
+}
+
+impl ProdScheduler {
+    /// Constructs a new scheduler, which does not know of any change of the validator set yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The heights after which the validator set is known to change.
+    pub fn validator_set_changes(&self) -> Vec<Height> {
+        self.validator_set_changes
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Record the changes of the validator set visible in the given light blocks.
+    fn record_validator_set_changes(&self, light_blocks: &[LightBlock]) {
+        let mut changes = self.validator_set_changes.lock().unwrap();
+
+        for light_block in light_blocks {
+            let header = &light_block.signed_header.header;
+            if header.validators_hash != header.next_validators_hash {
+                changes.insert(light_block.height());
+            }
+        }
+    }
+
+    /// Forget the changes of the validator set which are not strictly between the
+    /// given trusted and target heights, and the highest ones beyond
+    /// `MAX_VALIDATOR_SET_CHANGES`.
+    fn prune_validator_set_changes(&self, trusted_height: Height, target_height: Height) {
+        let mut changes = self.validator_set_changes.lock().unwrap();
+
+        *changes = if trusted_height < target_height {
+            changes
+                .range(trusted_height + 1..target_height)
+                .take(MAX_VALIDATOR_SET_CHANGES)
+                .copied()
+                .collect()
+        } else {
+            BTreeSet::new()
+        };
+    }
+
+    /// Pick the best height strictly between `low` and `high`, amongst the given
+    /// unverified heights and the known changes of the validator set.
+    #[pre(low + 1 < high)]
+    #[post(low < ret && ret < high)]
+    fn pick_between(&self, low: Height, high: Height, unverified: &[Height]) -> Height {
+        let middle = midpoint(low, high);
+        let changes = self.validator_set_changes.lock().unwrap();
+
+        unverified
+            .iter()
+            .chain(changes.range(low + 1..high))
+            .copied()
+            .filter(|h| low < *h && *h < high)
+            .min_by_key(|h| if *h < middle { middle - h } else { h - middle })
+            .unwrap_or(middle)
+    }
+}
+
+#[contract_trait]
+impl Scheduler for ProdScheduler {
+    fn schedule(
+        &self,
+        light_store: &dyn LightStore,
+        current_height: Height,
+        target_height: Height,
+    ) -> Height {
+        let trusted_height = light_store
+            .highest(VerifiedStatus::Verified)
+            .map(|lb| lb.height())
+            .unwrap();
+
+        let unverified = light_store
+            .all(VerifiedStatus::Unverified)
+            .collect::<Vec<_>>();
+
+        self.record_validator_set_changes(&unverified);
+        self.prune_validator_set_changes(trusted_height, target_height);
+
+        let unverified = unverified.iter().map(|lb| lb.height()).collect::<Vec<_>>();
+
+        if trusted_height == current_height && trusted_height < target_height {
+            target_height
+        } else if trusted_height < current_height && trusted_height < target_height {
+            if trusted_height + 1 < current_height {
+                self.pick_between(trusted_height, current_height, &unverified)
+            } else {
+                current_height
+            }
+        } else if trusted_height == target_height {
+            target_height
+        } else {
+            midpoint(current_height, target_height)
+        }
+    }
+}
+
 /// Checks whether the given `scheduled_height` is a valid schedule according to the
 /// following specification.
 ///
@@ -6,13 +6,24 @@ use crate::{
         CommitValidator, HeaderHasher, ProdCommitValidator, ProdHeaderHasher,
         ProdVotingPowerCalculator, VotingPowerCalculator,
     },
-    types::LightBlock,
+    types::{Hash, LightBlock},
+};
+use preds::{
+    errors::VerificationError, report::VerificationReport, ProdPredicates, VerificationPredicates,
+};
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
 };
-use preds::{errors::VerificationError, ProdPredicates, VerificationPredicates};
 
 /// Represents the result of the verification performed by the
 /// verifier component.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Verdict {
     /// Verification succeeded, the block is valid.
     Success,
@@ -42,9 +53,23 @@ impl From<Result<(), VerificationError>> for Verdict {
 /// ## Implements
 /// - [TMBC-VAL-CONTAINS-CORR.1]
 /// - [TMBC-VAL-COMMIT.1]
-pub trait Verifier: Send {
+pub trait Verifier: Send + Sync {
     /// Perform the verification.
     fn verify(&self, untrusted: &LightBlock, trusted: &LightBlock, options: &Options) -> Verdict;
+
+    /// Perform the verification of the given untrusted light block against
+    /// the trusted light block right above it, by following the hash link
+    /// between their headers.
+    ///
+    /// As hash links do not expire, the trusting period only applies to the block
+    /// the backward verification starts from, which the light client checks itself.
+    ///
+    /// Verifiers which do not support backward verification reject every block.
+    fn verify_backward(&self, _untrusted: &LightBlock, _trusted: &LightBlock) -> Verdict {
+        Verdict::Invalid(VerificationError::ImplementationSpecific(
+            "backward verification is not supported by this verifier".to_string(),
+        ))
+    }
 }
 
 /// Production implementation of the verifier.
@@ -77,6 +102,25 @@ impl ProdVerifier {
             header_hasher: Box::new(header_hasher),
         }
     }
+
+    /// Evaluate all the predicates involved in the verification of the given untrusted
+    /// light block, and report their outcome. See `predicates::explain` for details.
+    pub fn explain(
+        &self,
+        untrusted: &LightBlock,
+        trusted: &LightBlock,
+        options: &Options,
+    ) -> VerificationReport {
+        preds::explain(
+            &*self.predicates,
+            &*self.voting_power_calculator,
+            &*self.commit_validator,
+            &*self.header_hasher,
+            &trusted,
+            &untrusted,
+            options,
+        )
+    }
 }
 
 impl Default for ProdVerifier {
@@ -103,4 +147,229 @@ impl Verifier for ProdVerifier {
         )
         .into()
     }
+
+    fn verify_backward(&self, untrusted: &LightBlock, trusted: &LightBlock) -> Verdict {
+        preds::verify_backward(
+            &*self.predicates,
+            &*self.header_hasher,
+            &trusted,
+            &untrusted,
+        )
+        .into()
+    }
+}
+
+/// Default number of verification results kept by a `MemoizingVerifier`.
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// Verifier which remembers the verdicts of another verifier, so that verifying
+/// the same pair of light blocks again, eg. during fork detection or when bisection
+/// is retried, does not redo the signature verification work.
+///
+/// The verdicts are kept in a bounded LRU cache, keyed by the hashes of the trusted
+/// and untrusted headers and by the verification options. As the current time keeps
+/// changing, it is not part of the key: instead, the trusted light block is checked
+/// to still be within the trusting period upon every cache hit, and verdicts which
+/// depend on the current time are never cached.
+///
+/// Backward verification is cheap and is thus delegated as is to the inner verifier.
+pub struct MemoizingVerifier {
+    inner: Box<dyn Verifier>,
+    header_hasher: Box<dyn HeaderHasher>,
+    cache: Mutex<VerdictCache>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl MemoizingVerifier {
+    /// Wrap the given verifier, keeping at most `capacity` verdicts around.
+    pub fn new(inner: impl Verifier + 'static, capacity: usize) -> Self {
+        Self::with_header_hasher(inner, ProdHeaderHasher, capacity)
+    }
+
+    /// Wrap the given verifier, keeping at most `capacity` verdicts around,
+    /// and using the given header hasher to compute the cache keys.
+    pub fn with_header_hasher(
+        inner: impl Verifier + 'static,
+        header_hasher: impl HeaderHasher + 'static,
+        capacity: usize,
+    ) -> Self {
+        Self {
+            inner: Box::new(inner),
+            header_hasher: Box::new(header_hasher),
+            cache: Mutex::new(VerdictCache::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The number of verifications answered from the cache so far.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    /// The number of verifications delegated to the inner verifier so far.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::SeqCst)
+    }
+
+    fn key(&self, untrusted: &LightBlock, trusted: &LightBlock, options: &Options) -> CacheKey {
+        CacheKey {
+            trusted_hash: self.header_hasher.hash(&trusted.signed_header.header),
+            untrusted_hash: self.header_hasher.hash(&untrusted.signed_header.header),
+            trust_threshold: (
+                options.trust_threshold.numerator,
+                options.trust_threshold.denominator,
+            ),
+            trusting_period: options.trusting_period,
+            clock_drift: options.clock_drift,
+        }
+    }
+}
+
+impl Verifier for MemoizingVerifier {
+    fn verify(&self, untrusted: &LightBlock, trusted: &LightBlock, options: &Options) -> Verdict {
+        let key = self.key(untrusted, trusted, options);
+
+        let cached = self.cache.lock().unwrap().get(&key, untrusted, trusted);
+
+        if let Some(verdict) = cached {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+
+            // The verdict might have been cached while the trusted block was still
+            // within the trusting period, let's make sure it still is.
+            let within_trust_period = ProdPredicates.is_within_trust_period(
+                &trusted.signed_header.header,
+                options.trusting_period,
+                options.clock_drift,
+                options.now,
+            );
+
+            return match within_trust_period {
+                Ok(()) => verdict,
+                Err(e) => Verdict::Invalid(e),
+            };
+        }
+
+        self.misses.fetch_add(1, Ordering::SeqCst);
+
+        let verdict = self.inner.verify(untrusted, trusted, options);
+
+        if !depends_on_time(&verdict) {
+            self.cache
+                .lock()
+                .unwrap()
+                .insert(key, untrusted, trusted, verdict.clone());
+        }
+
+        verdict
+    }
+
+    fn verify_backward(&self, untrusted: &LightBlock, trusted: &LightBlock) -> Verdict {
+        self.inner.verify_backward(untrusted, trusted)
+    }
+}
+
+/// Whether the given verdict depends on the current time, and thus cannot be cached.
+fn depends_on_time(verdict: &Verdict) -> bool {
+    match verdict {
+        Verdict::Success => false,
+        Verdict::NotEnoughTrust(_) => false,
+        Verdict::Invalid(VerificationError::HeaderFromTheFuture { .. }) => true,
+        Verdict::Invalid(e) => e.has_expired(),
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct CacheKey {
+    trusted_hash: Hash,
+    untrusted_hash: Hash,
+    trust_threshold: (u64, u64),
+    trusting_period: Duration,
+    clock_drift: Duration,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    untrusted: LightBlock,
+    trusted: LightBlock,
+    verdict: Verdict,
+    last_used: u64,
+}
+
+/// Bounded cache of verdicts, which evicts the least recently used one when full.
+///
+/// The light blocks are kept alongside their verdict, and compared with the ones being
+/// verified on lookup, as the header hashes alone do not cover the commit nor the
+/// validator sets of the light blocks.
+#[derive(Debug)]
+struct VerdictCache {
+    capacity: usize,
+    clock: u64,
+    entries: HashMap<CacheKey, CacheEntry>,
+}
+
+impl VerdictCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(
+        &mut self,
+        key: &CacheKey,
+        untrusted: &LightBlock,
+        trusted: &LightBlock,
+    ) -> Option<Verdict> {
+        self.clock += 1;
+
+        let entry = self.entries.get_mut(key)?;
+        if entry.untrusted != *untrusted || entry.trusted != *trusted {
+            return None;
+        }
+
+        entry.last_used = self.clock;
+        Some(entry.verdict.clone())
+    }
+
+    fn insert(
+        &mut self,
+        key: CacheKey,
+        untrusted: &LightBlock,
+        trusted: &LightBlock,
+        verdict: Verdict,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.clock += 1;
+
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            // Linear in the capacity, which is fine as this is dwarfed by the cost
+            // of the verification we just performed.
+            let lru = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key);
+
+            if let Some(lru) = lru {
+                self.entries.remove(&lru);
+            }
+        }
+
+        self.entries.insert(
+            key,
+            CacheEntry {
+                untrusted: untrusted.clone(),
+                trusted: trusted.clone(),
+                verdict,
+                last_used: self.clock,
+            },
+        );
+    }
 }
@@ -1,14 +1,16 @@
 //! Toplevel errors raised by the light client.
 
+use std::fmt;
+
 use anomaly::{BoxError, Context};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    components::io::IoError,
+    components::{clock::Skew, io::IoError},
     light_client::Options,
     predicates::errors::VerificationError,
-    types::{Height, LightBlock, PeerId},
+    types::{ChainId, Height, LightBlock, PeerId},
 };
 
 pub type Error = anomaly::Error<ErrorKind>;
@@ -21,6 +23,12 @@ pub enum ErrorKind {
     #[error("store error")]
     Store,
 
+    #[error("unsupported store schema version {found}, latest supported version is {supported}")]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
+
+    #[error("light block from chain {found} cannot be stored for chain {expected}")]
+    ChainIdMismatch { expected: ChainId, found: ChainId },
+
     #[error("no witnesses")]
     NoWitnesses,
 
@@ -44,6 +52,9 @@ pub enum ErrorKind {
 
     #[error("invalid light block: {0}")]
     InvalidLightBlock(#[source] VerificationError),
+
+    #[error("local clock is skewed relative to the peers: {0:?}")]
+    ClockSkewed(Skew),
 }
 
 impl ErrorKind {
@@ -54,6 +65,48 @@ impl ErrorKind {
     }
 }
 
+/// Duplicate the given error, along with its chain of sources.
+///
+/// As errors cannot be cloned, the sources of the duplicate are a `SourceChain`,
+/// which retains the messages of the original sources, but not their types.
+pub fn duplicate(e: &Error) -> Error {
+    match std::error::Error::source(e) {
+        Some(source) => e.kind().clone().context(SourceChain::new(source)).into(),
+        None => e.kind().clone().into(),
+    }
+}
+
+/// The messages of a chain of error sources. See `duplicate`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceChain {
+    message: String,
+    source: Option<Box<SourceChain>>,
+}
+
+impl SourceChain {
+    /// Record the messages of the given error and of its chain of sources.
+    pub fn new(e: &(dyn std::error::Error + 'static)) -> Self {
+        Self {
+            message: e.to_string(),
+            source: e.source().map(|source| Box::new(Self::new(source))),
+        }
+    }
+}
+
+impl fmt::Display for SourceChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SourceChain {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| source.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
+
 pub trait ErrorExt {
     /// Whether this error means that the light block
     /// cannot be trusted w.r.t. the latest trusted state.
@@ -0,0 +1,128 @@
+//! Fork evidence data structures and interfaces.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tendermint::lite::ValidatorSet as _;
+
+use tendermint_rpc as rpc;
+
+use crate::{
+    components::io::{with_timeout, IoError},
+    types::{Hash, LightBlock, PeerId},
+};
+
+pub use tendermint::evidence::{Evidence, LightClientAttackEvidence};
+
+/// Build a light client attack evidence from the given conflicting light block,
+/// and the last light block which was trusted by both the primary and the witness.
+pub fn light_client_attack(conflicting_block: &LightBlock, common_block: &LightBlock) -> Evidence {
+    Evidence::LightClientAttack(Box::new(LightClientAttackEvidence {
+        conflicting_header: conflicting_block.signed_header.clone(),
+        conflicting_validators: conflicting_block.validators.clone(),
+        common_height: common_block.signed_header.header.height,
+        total_voting_power: common_block.validators.total_power(),
+        timestamp: common_block.signed_header.header.time,
+    }))
+}
+
+/// Interface for reporting evidence to full nodes, typically via the RPC client.
+#[async_trait]
+pub trait EvidenceReporter: Send + Sync {
+    /// Report the given evidence to the peer with the given peer ID,
+    /// and return the hash of the evidence as computed by the peer.
+    async fn report(&self, evidence: Evidence, peer: PeerId) -> Result<Hash, IoError>;
+}
+
+/// Production implementation of the EvidenceReporter component, which reports
+/// evidence to full nodes via RPC.
+///
+/// As for `ProdIo`, a single RPC client is kept per peer, so that the connection
+/// to a peer is reused across reports.
+#[derive(Clone, Debug)]
+pub struct ProdEvidenceReporter {
+    rpc_clients: HashMap<PeerId, rpc::Client>,
+    timeout: Option<Duration>,
+}
+
+#[async_trait]
+impl EvidenceReporter for ProdEvidenceReporter {
+    /// ## Precondition
+    /// - The given peer is part of the peer map.
+    async fn report(&self, evidence: Evidence, peer: PeerId) -> Result<Hash, IoError> {
+        let rpc_client = self.rpc_client_for(peer);
+
+        let res = with_timeout(rpc_client.broadcast_evidence(evidence), peer, self.timeout).await?;
+
+        match res {
+            Ok(response) => Ok(response.hash),
+            Err(err) => Err(IoError::from(err)),
+        }
+    }
+}
+
+impl ProdEvidenceReporter {
+    /// Constructs a new ProdEvidenceReporter component.
+    ///
+    /// A peer map which maps peer IDS to their network address must be supplied.
+    pub fn new(
+        peer_map: HashMap<PeerId, tendermint::net::Address>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        let rpc_clients = peer_map
+            .into_iter()
+            .map(|(peer, address)| (peer, rpc::Client::new(address)))
+            .collect();
+
+        Self {
+            rpc_clients,
+            timeout,
+        }
+    }
+
+    /// ## Precondition
+    /// - The given peer is part of the peer map.
+    fn rpc_client_for(&self, peer: PeerId) -> rpc::Client {
+        self.rpc_clients.get(&peer).unwrap().clone()
+    }
+}
+
+/// Transient in-memory evidence reporter, which records the evidence
+/// reported to each peer instead of submitting it, for testing purposes.
+///
+/// Clones of a `MemoryEvidenceReporter` share the same record, so that
+/// one can keep a clone around to inspect what the supervisor reported.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryEvidenceReporter {
+    reported: Arc<Mutex<Vec<(PeerId, Evidence)>>>,
+}
+
+impl MemoryEvidenceReporter {
+    /// Constructs a new MemoryEvidenceReporter, which has not recorded any evidence yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the list of peers and the evidence reported to them, in reporting order.
+    pub fn reported(&self) -> Vec<(PeerId, Evidence)> {
+        self.reported.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl EvidenceReporter for MemoryEvidenceReporter {
+    /// Record the given evidence, and return the hash
+    /// of the conflicting header in lieu of the evidence hash.
+    async fn report(&self, evidence: Evidence, peer: PeerId) -> Result<Hash, IoError> {
+        let hash = match &evidence {
+            Evidence::LightClientAttack(e) => e.conflicting_header.commit.block_id.hash,
+            Evidence::DuplicateVote(_) => Hash::Sha256([0; 32]),
+        };
+
+        self.reported.lock().unwrap().push((peer, evidence));
+
+        Ok(hash)
+    }
+}
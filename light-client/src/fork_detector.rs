@@ -1,12 +1,17 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    components::io::{with_timeout, IoError},
     errors::{Error, ErrorExt, ErrorKind},
     operations::{HeaderHasher, ProdHeaderHasher},
     state::State,
     store::{memory::MemoryStore, VerifiedStatus},
     supervisor::Instance,
-    types::LightBlock,
+    types::{Hash, Height, LightBlock, PeerId},
 };
 
 /// Result of fork detection
@@ -22,19 +27,44 @@ pub enum ForkDetection {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Fork {
     /// An actual fork was found for this `LightBlock`
-    Forked(LightBlock),
+    Forked(LightBlock, Divergence),
+    /// This `LightBlock` conflicts with the primary, but could not be verified
+    /// against the common trusted state because the trusting period has expired.
+    /// This is a potential fork.
+    LackOfTrust(LightBlock, Divergence, ErrorKind),
     /// The node has been deemed faulty for this `LightBlock`
     Faulty(LightBlock, ErrorKind),
+    /// The node did not respond in time, or could not serve the requested blocks
+    Timeout(PeerId, ErrorKind),
+    /// The node served a response which is inconsistent with the request
+    InvalidResponse(PeerId, ErrorKind),
+}
+
+/// Describes where the chains of the primary and of a witness diverge.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Divergence {
+    /// The lowest height at which the primary and the witness disagree
+    pub height: Height,
+    /// The light blocks the primary needed to verify its block, highest first
+    pub primary_trace: Vec<LightBlock>,
+    /// The light blocks the witness needed to verify its conflicting block, highest first
+    pub witness_trace: Vec<LightBlock>,
 }
 
 /// Interface for a fork detector
-pub trait ForkDetector: Send {
-    /// Detect forks using the given light block, trusted state,
-    /// and list of witnesses to verify the given light block against.
-    fn detect_forks(
+#[async_trait]
+pub trait ForkDetector: Send + Sync {
+    /// Detect forks using the given light block verified by the primary,
+    /// the trusted state it was verified from, and list of witnesses to
+    /// verify the given light block against.
+    ///
+    /// ## Precondition
+    /// - The given light block is higher than the trusted state
+    async fn detect_forks(
         &self,
         light_block: &LightBlock,
         trusted_state: &LightBlock,
+        primary: &Instance,
         witnesses: Vec<&Instance>,
     ) -> Result<ForkDetection, Error>;
 }
@@ -44,74 +74,255 @@ pub trait ForkDetector: Send {
 /// If the hashes don't match, this fork detector
 /// then attempts to verify the light block pulled from
 /// the witness against a light block containing only
-/// the given trusted state, and then:
+/// the given trusted state, bisecting on the witness
+/// side if needed, and then:
 ///
 /// - If the verification succeeds, we have a real fork
-/// - If verification fails because of lack of trust,
-///   we have a potential fork.
+/// - If verification fails because the trusting period
+///   has expired, we have a potential fork.
+/// - If verification fails because the witness could not
+///   serve the blocks needed to verify its light block,
+///   the witness is reported as `Fork::Timeout` or
+///   `Fork::InvalidResponse`, see below.
 /// - If verification fails for any other reason, the
 ///   witness is deemed faulty.
+///
+/// For real and potential forks, the detector then bisects
+/// between the trusted state and the conflicting block to find
+/// the lowest height at which the primary and the witness disagree,
+/// and reports it along with the verification trace of both sides.
+///
+/// All the witnesses are queried concurrently. If a `timeout` is given,
+/// a witness which does not complete within the timeout is reported as
+/// `Fork::Timeout`, without holding back the other witnesses, as is a witness
+/// which cannot be reached. A witness which serves an invalid response is
+/// reported as `Fork::InvalidResponse`.
 pub struct ProdForkDetector {
     header_hasher: Box<dyn HeaderHasher>,
+    timeout: Option<Duration>,
 }
 
 impl ProdForkDetector {
-    /// Construct a new fork detector that will use the given header hasher.
-    pub fn new(header_hasher: impl HeaderHasher + 'static) -> Self {
+    /// Construct a new fork detector that will use the given header hasher,
+    /// and give up on a witness after the given timeout, if any.
+    pub fn new(header_hasher: impl HeaderHasher + 'static, timeout: Option<Duration>) -> Self {
         Self {
             header_hasher: Box::new(header_hasher),
+            timeout,
         }
     }
-}
-
-impl Default for ProdForkDetector {
-    fn default() -> Self {
-        Self::new(ProdHeaderHasher)
-    }
-}
 
-impl ForkDetector for ProdForkDetector {
-    /// Perform fork detection. See the documentation `ProdForkDetector` for details.
-    fn detect_forks(
+    /// Perform fork detection against a single witness.
+    async fn detect_fork_with(
         &self,
+        primary: &Instance,
+        witness: &Instance,
         light_block: &LightBlock,
+        primary_hash: Hash,
         trusted_state: &LightBlock,
-        witnesses: Vec<&Instance>,
-    ) -> Result<ForkDetection, Error> {
-        let primary_hash = self.header_hasher.hash(&light_block.signed_header.header);
+    ) -> Result<Option<Fork>, Error> {
+        let mut state = State::new(MemoryStore::new());
+
+        let witness_block = witness
+            .light_client
+            .get_or_fetch_block_async(light_block.height(), &mut state)
+            .await?;
+
+        let witness_hash = self.header_hasher.hash(&witness_block.signed_header.header);
+
+        if primary_hash == witness_hash {
+            // Hashes match, no fork with this witness.
+            return Ok(None);
+        }
+
+        state
+            .light_store
+            .update(trusted_state.clone(), VerifiedStatus::Verified)?;
+
+        state
+            .light_store
+            .update(witness_block.clone(), VerifiedStatus::Unverified)?;
+
+        let result = witness
+            .light_client
+            .verify_to_target_async(light_block.height(), &mut state)
+            .await;
+
+        let lack_of_trust = match result {
+            Ok(_) => None,
+            Err(e) if is_lack_of_trust(e.kind()) => Some(e.kind().clone()),
+            // The witness failed to serve the blocks needed for verification,
+            // which is classified by `detect_fork_with_timeout`.
+            Err(e) if matches!(e.kind(), ErrorKind::Io(_)) => return Err(e),
+            Err(e) => return Ok(Some(Fork::Faulty(witness_block, e.kind().clone()))),
+        };
+
+        let primary_trace = primary.state.get_trace(light_block.height());
+        let witness_trace = state.get_trace(light_block.height());
+
+        let height = self
+            .find_divergence(
+                primary,
+                witness,
+                &primary_trace,
+                &mut state,
+                trusted_state.height(),
+                light_block.height(),
+            )
+            .await?;
+
+        let divergence = Divergence {
+            height,
+            primary_trace,
+            witness_trace,
+        };
+
+        match lack_of_trust {
+            None => Ok(Some(Fork::Forked(witness_block, divergence))),
+            Some(e) => Ok(Some(Fork::LackOfTrust(witness_block, divergence, e))),
+        }
+    }
+
+    /// Bisect between the given heights to find the lowest height at which the
+    /// primary and the witness disagree, re-using the light blocks from the
+    /// primary trace and from the witness state whenever possible.
+    ///
+    /// ## Precondition
+    /// - The primary and the witness agree at height `agreed_height`
+    /// - The primary and the witness disagree at height `conflicting_height`
+    /// - `agreed_height` is lower than `conflicting_height`
+    async fn find_divergence(
+        &self,
+        primary: &Instance,
+        witness: &Instance,
+        primary_trace: &[LightBlock],
+        witness_state: &mut State,
+        agreed_height: Height,
+        conflicting_height: Height,
+    ) -> Result<Height, Error> {
+        let mut primary_state = State::new(MemoryStore::new());
+        for light_block in primary_trace {
+            primary_state
+                .light_store
+                .insert(light_block.clone(), VerifiedStatus::Verified)?;
+        }
 
-        let mut forks = Vec::with_capacity(witnesses.len());
+        assert!(
+            agreed_height < conflicting_height,
+            "agreed height {} is not lower than conflicting height {}",
+            agreed_height,
+            conflicting_height
+        );
 
-        for witness in witnesses {
-            let mut state = State::new(MemoryStore::new());
+        let (mut low, mut high) = (agreed_height, conflicting_height);
+
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+
+            let primary_block = primary
+                .light_client
+                .get_or_fetch_block_async(middle, &mut primary_state)
+                .await?;
 
             let witness_block = witness
                 .light_client
-                .get_or_fetch_block(light_block.height(), &mut state)?;
+                .get_or_fetch_block_async(middle, witness_state)
+                .await?;
 
+            let primary_hash = self.header_hasher.hash(&primary_block.signed_header.header);
             let witness_hash = self.header_hasher.hash(&witness_block.signed_header.header);
 
             if primary_hash == witness_hash {
-                // Hashes match, continue with next witness, if any.
-                continue;
+                low = middle;
+            } else {
+                high = middle;
             }
+        }
 
-            state
-                .light_store
-                .update(trusted_state.clone(), VerifiedStatus::Verified);
+        Ok(high)
+    }
 
-            state
-                .light_store
-                .update(witness_block.clone(), VerifiedStatus::Unverified);
+    /// Perform fork detection against a single witness, within the configured timeout.
+    async fn detect_fork_with_timeout(
+        &self,
+        primary: &Instance,
+        witness: &Instance,
+        light_block: &LightBlock,
+        primary_hash: Hash,
+        trusted_state: &LightBlock,
+    ) -> Result<Option<Fork>, Error> {
+        let peer = witness.light_client.peer;
 
-            let result = witness
-                .light_client
-                .verify_to_target(light_block.height(), &mut state);
+        let result = with_timeout(
+            self.detect_fork_with(primary, witness, light_block, primary_hash, trusted_state),
+            peer,
+            self.timeout,
+        )
+        .await
+        .map_err(|e| Error::from(ErrorKind::Io(e)))
+        .and_then(|result| result);
+
+        match result {
+            Err(e) => match e.kind() {
+                ErrorKind::Io(IoError::InvalidResponse(_)) => {
+                    Ok(Some(Fork::InvalidResponse(peer, e.kind().clone())))
+                }
+                ErrorKind::Io(_) => Ok(Some(Fork::Timeout(peer, e.kind().clone()))),
+                _ => Err(e),
+            },
+            outcome => outcome,
+        }
+    }
+}
+
+/// Whether the given error means that the conflicting block could not be
+/// verified against the trusted state because the trusting period has expired,
+/// rather than because the blocks provided by the witness are invalid.
+///
+/// A witness whose blocks cannot be verified within the trusting period, eg. because
+/// bisection fails, is deemed faulty rather than reported as a potential fork,
+/// as it would otherwise make every verification fail without ever being penalized.
+fn is_lack_of_trust(e: &ErrorKind) -> bool {
+    match e {
+        ErrorKind::TrustedStateOutsideTrustingPeriod { .. } => true,
+        e => e.has_expired(),
+    }
+}
+
+impl Default for ProdForkDetector {
+    fn default() -> Self {
+        Self::new(ProdHeaderHasher, None)
+    }
+}
+
+#[async_trait]
+impl ForkDetector for ProdForkDetector {
+    /// Perform fork detection. See the documentation `ProdForkDetector` for details.
+    async fn detect_forks(
+        &self,
+        light_block: &LightBlock,
+        trusted_state: &LightBlock,
+        primary: &Instance,
+        witnesses: Vec<&Instance>,
+    ) -> Result<ForkDetection, Error> {
+        let primary_hash = self.header_hasher.hash(&light_block.signed_header.header);
+
+        let outcomes = join_all(witnesses.into_iter().map(|witness| {
+            self.detect_fork_with_timeout(
+                primary,
+                witness,
+                light_block,
+                primary_hash,
+                trusted_state,
+            )
+        }))
+        .await;
+
+        let mut forks = Vec::with_capacity(outcomes.len());
 
-            match result {
-                Ok(_) => forks.push(Fork::Forked(witness_block)),
-                Err(e) if e.kind().has_expired() => forks.push(Fork::Forked(witness_block)),
-                Err(e) => forks.push(Fork::Faulty(witness_block, e.kind().clone())),
+        for outcome in outcomes {
+            if let Some(fork) = outcome? {
+                forks.push(fork);
             }
         }
 
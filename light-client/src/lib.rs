@@ -12,14 +12,17 @@
 
 //! See the `light_client` module for the main documentation.
 
+pub mod auto_sync;
 pub mod callback;
 pub mod components;
 pub mod contracts;
 pub mod errors;
+pub mod evidence;
 pub mod fork_detector;
 pub mod light_client;
 pub mod operations;
 pub mod peer_list;
+pub mod peer_score;
 pub mod predicates;
 pub mod state;
 pub mod store;
@@ -5,18 +5,44 @@
 use contracts::*;
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
-use std::{fmt, time::Duration};
+use std::{
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
-use crate::components::{clock::Clock, io::*, scheduler::*, verifier::*};
+use crate::components::{
+    clock::{Clock, Skew},
+    io::*,
+    scheduler::*,
+    verifier::*,
+};
 use crate::contracts::*;
 use crate::{
     bail,
     errors::{Error, ErrorKind},
     state::State,
-    store::VerifiedStatus,
-    types::{Height, LightBlock, PeerId, Time, TrustThreshold},
+    store::{LightStore, VerifiedStatus},
+    types::{Height, LightBlock, PeerId, Time, TrustThreshold, ValidatorSet},
 };
 
+/// How the light client reaches the target height from the trusted state.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerificationMode {
+    /// Skip as many intermediate headers as possible, bisecting whenever a header
+    /// cannot be trusted yet.
+    Skipping,
+    /// Verify every header between the trusted state and the target height, in order,
+    /// checking that each header is signed by the next validators of the previous one.
+    Sequential,
+}
+
+impl Default for VerificationMode {
+    fn default() -> Self {
+        Self::Skipping
+    }
+}
+
 /// Verification parameters
 ///
 /// TODO: Find a better name than `Options`
@@ -33,6 +59,9 @@ pub struct Options {
     pub clock_drift: Duration,
     /// The current time
     pub now: Time,
+    /// How to reach the target height from the trusted state
+    #[serde(default)]
+    pub verification_mode: VerificationMode,
 }
 
 impl Options {
@@ -52,13 +81,22 @@ impl Options {
 /// of the header, more than two-thirds of the next validators of a new block are
 /// correct for the duration of the trusted period.  The fault-tolerant read operation
 /// is designed for this security model.
+///
+/// The light client can either be driven synchronously, in which case the calls run on
+/// a runtime owned by the light client, or asynchronously via the methods suffixed with `_async`,
+/// eg. from within an existing `tokio` runtime.
+/// In the latter case, the light client should be constructed with `LightClient::new_async`
+/// and an `AsyncIo` component, so that fetching light blocks does not block the current thread.
 pub struct LightClient {
     pub peer: PeerId,
     pub options: Options,
     clock: Box<dyn Clock>,
     scheduler: Box<dyn Scheduler>,
     verifier: Box<dyn Verifier>,
-    io: Box<dyn Io>,
+    io: Box<dyn AsyncIo>,
+    latency: Mutex<Option<Duration>>,
+    unrecorded_latency: Mutex<Option<Duration>>,
+    runtime: Runtime,
 }
 
 impl fmt::Debug for LightClient {
@@ -71,7 +109,8 @@ impl fmt::Debug for LightClient {
 }
 
 impl LightClient {
-    /// Constructs a new light client
+    /// Constructs a new light client which fetches light blocks
+    /// with the given synchronous `Io` component.
     pub fn new(
         peer: PeerId,
         options: Options,
@@ -79,6 +118,29 @@ impl LightClient {
         scheduler: impl Scheduler + 'static,
         verifier: impl Verifier + 'static,
         io: impl Io + 'static,
+    ) -> Self {
+        Self::new_async(
+            peer,
+            options,
+            clock,
+            scheduler,
+            verifier,
+            BlockingIo::new(io),
+        )
+    }
+
+    /// Constructs a new light client which fetches light blocks
+    /// with the given asynchronous `AsyncIo` component.
+    ///
+    /// ## Note
+    /// Such a light client is meant to be driven via its `async` methods.
+    pub fn new_async(
+        peer: PeerId,
+        options: Options,
+        clock: impl Clock + 'static,
+        scheduler: impl Scheduler + 'static,
+        verifier: impl Verifier + 'static,
+        io: impl AsyncIo + 'static,
     ) -> Self {
         Self {
             peer,
@@ -87,19 +149,74 @@ impl LightClient {
             scheduler: Box::new(scheduler),
             verifier: Box::new(verifier),
             io: Box::new(io),
+            latency: Mutex::new(None),
+            unrecorded_latency: Mutex::new(None),
+            runtime: Runtime::default(),
         }
     }
 
+    /// The current time, as per the clock of this light client.
+    pub fn now(&self) -> Time {
+        self.clock.now()
+    }
+
+    /// Record the given times of the latest headers of several peers in the clock of
+    /// this light client, for it to estimate its skew. See `Clock::record_header_times`.
+    pub fn record_header_times(&self, header_times: &[Time]) -> Option<Skew> {
+        self.clock.record_header_times(header_times)
+    }
+
+    /// Whether the clock of this light client deems itself skewed. See `Clock::is_skewed`.
+    pub fn is_clock_skewed(&self) -> bool {
+        self.clock.is_skewed()
+    }
+
+    /// Fetch the time of the latest header of our peer.
+    pub async fn latest_header_time_async(&self) -> Result<Time, Error> {
+        let signed_header = self
+            .io
+            .fetch_signed_header(self.peer, AtHeight::Highest)
+            .await
+            .map_err(ErrorKind::Io)?;
+
+        Ok(signed_header.header.time)
+    }
+
+    /// The time it took for the latest light block request to our peer to complete, if any.
+    pub fn latency(&self) -> Option<Duration> {
+        *self.latency.lock().unwrap()
+    }
+
+    /// The time it took for the latest successful light block request to our peer to complete,
+    /// if there has been one since the last call to this method.
+    pub fn take_latency(&self) -> Option<Duration> {
+        self.unrecorded_latency.lock().unwrap().take()
+    }
+
     /// Attempt to update the light client to the highest block of the primary node.
     ///
     /// Note: This functin delegates the actual work to `verify_to_target`.
     pub fn verify_to_highest(&mut self, state: &mut State) -> Result<LightBlock, Error> {
-        let target_block = match self.io.fetch_light_block(self.peer, AtHeight::Highest) {
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.verify_to_highest_async(state))
+    }
+
+    /// Async version of `verify_to_highest`.
+    pub async fn verify_to_highest_async(
+        &mut self,
+        state: &mut State,
+    ) -> Result<LightBlock, Error> {
+        let target_block = match self
+            .io
+            .fetch_light_block(self.peer, AtHeight::Highest)
+            .await
+        {
             Ok(last_block) => last_block,
             Err(io_error) => bail!(ErrorKind::Io(io_error)),
         };
 
-        self.verify_to_target(target_block.height(), state)
+        self.verify_to_target_async(target_block.height(), state)
+            .await
     }
 
     /// Update the light client to a block of the primary node at the given height.
@@ -113,6 +230,14 @@ impl LightClient {
     /// - The Scheduler component decides which height to try to verify next, in case
     ///   the current block pass verification but cannot be trusted yet.
     ///
+    /// In `Sequential` mode, the scheduler is bypassed and every block between the highest
+    /// trusted block and the target height is verified in order, against the block right
+    /// below it.
+    ///
+    /// If the target height is below the highest trusted block, the light client instead
+    /// verifies the blocks down to the target height by following the hash links between
+    /// their headers, starting from the closest trusted block above the target height.
+    ///
     /// ## Implements
     /// - [LCV-DIST-SAFE.1]
     /// - [LCV-DIST-LIFE.1]
@@ -149,6 +274,16 @@ impl LightClient {
         &self,
         target_height: Height,
         state: &mut State,
+    ) -> Result<LightBlock, Error> {
+        self.runtime
+            .block_on(self.verify_to_target_async(target_height, state))
+    }
+
+    /// Async version of `verify_to_target`.
+    pub async fn verify_to_target_async(
+        &self,
+        target_height: Height,
+        state: &mut State,
     ) -> Result<LightBlock, Error> {
         // Let's first look in the store to see whether we have already successfully verified this block
         if let Some(light_block) = state
@@ -158,11 +293,34 @@ impl LightClient {
             return Ok(light_block);
         }
 
+        // Refuse to verify while the clock is known to be too skewed to be trusted.
+        if let Some(skew) = self.clock.excessive_skew() {
+            bail!(ErrorKind::ClockSkewed(skew));
+        }
+
         // Override the `now` fields in the given verification options with the current time,
         // as per the given `clock`.
         let options = self.options.with_now(self.clock.now());
 
-        let mut current_height = target_height;
+        let highest_trusted = state
+            .light_store
+            .highest(VerifiedStatus::Verified)
+            .ok_or_else(|| ErrorKind::NoInitialTrustedState)?;
+
+        if target_height < highest_trusted.height() {
+            return self
+                .verify_backward_async(target_height, &options, state)
+                .await;
+        }
+
+        let mut current_height = match options.verification_mode {
+            VerificationMode::Skipping => target_height,
+            VerificationMode::Sequential => sequential_schedule(
+                state.light_store.as_ref(),
+                highest_trusted.height(),
+                target_height,
+            ),
+        };
 
         loop {
             // Get the highest trusted state
@@ -189,7 +347,7 @@ impl LightClient {
             }
 
             // Fetch the block at the current height from our peer
-            let current_block = self.get_or_fetch_block(current_height, state)?;
+            let current_block = self.get_or_fetch_block_async(current_height, state).await?;
 
             // Validate and verify the current block
             let verdict = self
@@ -201,13 +359,24 @@ impl LightClient {
                     // Verification succeeded, add the block to the light store with `verified` status
                     state
                         .light_store
-                        .update(current_block, VerifiedStatus::Verified);
+                        .update(current_block, VerifiedStatus::Verified)?;
                 }
                 Verdict::Invalid(e) => {
                     // Verification failed, add the block to the light store with `failed` status, and abort.
                     state
                         .light_store
-                        .update(current_block, VerifiedStatus::Failed);
+                        .update(current_block, VerifiedStatus::Failed)?;
+
+                    bail!(ErrorKind::InvalidLightBlock(e))
+                }
+                Verdict::NotEnoughTrust(e)
+                    if options.verification_mode == VerificationMode::Sequential =>
+                {
+                    // Adjacent blocks never lack trust unless the peer sent us a block at
+                    // another height, in which case bisecting would not make any progress.
+                    state
+                        .light_store
+                        .update(current_block, VerifiedStatus::Failed)?;
 
                     bail!(ErrorKind::InvalidLightBlock(e))
                 }
@@ -218,17 +387,92 @@ impl LightClient {
                     // trusted state until there is enough overlap.
                     state
                         .light_store
-                        .update(current_block, VerifiedStatus::Unverified);
+                        .update(current_block, VerifiedStatus::Unverified)?;
                 }
             }
 
             // Compute the next height to fetch and verify
-            current_height =
-                self.scheduler
-                    .schedule(state.light_store.as_ref(), current_height, target_height);
+            current_height = match options.verification_mode {
+                VerificationMode::Skipping => self.scheduler.schedule(
+                    state.light_store.as_ref(),
+                    current_height,
+                    target_height,
+                ),
+                VerificationMode::Sequential => {
+                    sequential_schedule(state.light_store.as_ref(), current_height, target_height)
+                }
+            };
         }
     }
 
+    /// Verify the blocks below the closest trusted block above the given target height,
+    /// down to the target height, by checking that the hash of each header matches the
+    /// `last_block_id` of the header right above it.
+    ///
+    /// Each block verified along the way is added to the light store with `verified` status.
+    ///
+    /// ## Precondition
+    /// - The light store contains a verified block above `target_height`
+    ///
+    /// ## Errors
+    /// - If the closest trusted block above the target height is outside the trusting period
+    /// - If the hash link between two consecutive blocks is invalid
+    /// - If it cannot fetch a block from the blockchain
+    async fn verify_backward_async(
+        &self,
+        target_height: Height,
+        options: &Options,
+        state: &mut State,
+    ) -> Result<LightBlock, Error> {
+        let mut trusted_state = state
+            .light_store
+            .range(
+                target_height + 1..Height::max_value(),
+                VerifiedStatus::Verified,
+            )
+            .next()
+            .ok_or_else(|| ErrorKind::NoInitialTrustedState)?;
+
+        // Check invariant [LCV-INV-TP.1]
+        if !is_within_trust_period(&trusted_state, options.trusting_period, options.now) {
+            bail!(ErrorKind::TrustedStateOutsideTrustingPeriod {
+                trusted_state: Box::new(trusted_state),
+                options: *options,
+            });
+        }
+
+        while trusted_state.height() > target_height {
+            let current_height = trusted_state.height() - 1;
+
+            let current_block = self.get_or_fetch_block_async(current_height, state).await?;
+
+            let verdict = self
+                .verifier
+                .verify_backward(&current_block, &trusted_state);
+
+            match verdict {
+                Verdict::Success => {
+                    state
+                        .light_store
+                        .update(current_block.clone(), VerifiedStatus::Verified)?;
+
+                    trusted_state = current_block;
+                }
+                Verdict::Invalid(e) | Verdict::NotEnoughTrust(e) => {
+                    state
+                        .light_store
+                        .update(current_block, VerifiedStatus::Failed)?;
+
+                    bail!(ErrorKind::InvalidLightBlock(e))
+                }
+            }
+        }
+
+        state.trace_block(target_height, target_height);
+
+        Ok(trusted_state)
+    }
+
     /// Look in the light store for a block from the given peer at the given height.
     /// If one cannot be found, fetch the block from the given peer.
     ///
@@ -239,6 +483,16 @@ impl LightClient {
         &self,
         current_height: Height,
         state: &mut State,
+    ) -> Result<LightBlock, Error> {
+        self.runtime
+            .block_on(self.get_or_fetch_block_async(current_height, state))
+    }
+
+    /// Async version of `get_or_fetch_block`.
+    pub async fn get_or_fetch_block_async(
+        &self,
+        current_height: Height,
+        state: &mut State,
     ) -> Result<LightBlock, Error> {
         let current_block = state
             .light_store
@@ -253,15 +507,43 @@ impl LightClient {
             return Ok(current_block);
         }
 
-        self.io
-            .fetch_light_block(self.peer, AtHeight::At(current_height))
-            .map(|current_block| {
-                state
-                    .light_store
-                    .insert(current_block.clone(), VerifiedStatus::Unverified);
+        let known_validators = known_validators(state.light_store.as_ref(), current_height);
+        let started_at = Instant::now();
+
+        let result = self
+            .io
+            .fetch_light_block_with_validators(
+                self.peer,
+                AtHeight::At(current_height),
+                &known_validators,
+            )
+            .await;
+
+        let latency = started_at.elapsed();
+        *self.latency.lock().unwrap() = Some(latency);
 
-                current_block
-            })
-            .map_err(|e| ErrorKind::Io(e).into())
+        let current_block = result.map_err(ErrorKind::Io)?;
+        *self.unrecorded_latency.lock().unwrap() = Some(latency);
+
+        state
+            .light_store
+            .insert(current_block.clone(), VerifiedStatus::Unverified)?;
+
+        Ok(current_block)
     }
 }
+
+/// The validator sets of the closest verified light blocks below and above the given height,
+/// which the validator sets at that height are likely to be equal to.
+fn known_validators(light_store: &dyn LightStore, height: Height) -> Vec<ValidatorSet> {
+    let below = light_store.highest_below(height, VerifiedStatus::Verified);
+    let above = light_store
+        .range(height + 1..Height::max_value(), VerifiedStatus::Verified)
+        .next();
+
+    below
+        .into_iter()
+        .chain(above)
+        .flat_map(|light_block| vec![light_block.validators, light_block.next_validators])
+        .collect()
+}
@@ -8,3 +8,6 @@ pub use self::voting_power::*;
 
 pub mod commit_validator;
 pub use self::commit_validator::*;
+
+pub mod signatures;
+pub use self::signatures::*;
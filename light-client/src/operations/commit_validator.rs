@@ -7,7 +7,7 @@ use crate::{
 use anomaly::BoxError;
 use tendermint::lite::types::Commit as _;
 
-pub trait CommitValidator: Send {
+pub trait CommitValidator: Send + Sync {
     fn validate(
         &self,
         signed_header: &SignedHeader,
@@ -4,7 +4,7 @@ use tendermint::amino_types::{message::AminoMessage, BlockId, ConsensusVersion,
 use tendermint::merkle::simple_hash_from_byte_vectors;
 use tendermint::Hash;
 
-pub trait HeaderHasher: Send {
+pub trait HeaderHasher: Send + Sync {
     fn hash(&self, header: &Header) -> Hash; // Or Error?
 }
 
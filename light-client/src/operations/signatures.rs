@@ -0,0 +1,36 @@
+//! Verification of the signatures of a commit.
+
+use tendermint::validator;
+
+/// A signature to verify, along with the message and the validator it pertains to.
+#[derive(Clone, Debug)]
+pub struct SignatureCheck<'a> {
+    /// The validator who supposedly signed the message
+    pub validator: validator::Info,
+    /// The signed message
+    pub sign_bytes: Vec<u8>,
+    /// The signature of the message
+    pub signature: &'a [u8],
+}
+
+impl SignatureCheck<'_> {
+    /// Verify this signature on its own.
+    pub fn verify(&self) -> bool {
+        self.validator
+            .verify_signature(&self.sign_bytes, self.signature)
+    }
+}
+
+/// Verify all the given signatures, and return the index of the first invalid one, if any.
+///
+/// Each signature is verified on its own, with the same verification equation as the
+/// Tendermint consensus. The Ed25519 batch verification is not used: it checks the cofactored
+/// equation, whereas consensus checks the cofactorless one, so that a signature crafted with
+/// a small-order component would pass the batch while being rejected by consensus. Ruling out
+/// such components beforehand would cost more than the batch saves.
+pub fn verify_signatures(checks: &[SignatureCheck<'_>]) -> Result<(), usize> {
+    match checks.iter().position(|check| !check.verify()) {
+        Some(index) => Err(index),
+        None => Ok(()),
+    }
+}
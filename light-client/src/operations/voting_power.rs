@@ -1,5 +1,6 @@
 use crate::{
     bail,
+    operations::signatures::{verify_signatures, SignatureCheck},
     predicates::errors::VerificationError,
     types::{SignedHeader, ValidatorSet},
 };
@@ -7,7 +8,7 @@ use crate::{
 use anomaly::BoxError;
 use tendermint::lite::types::ValidatorSet as _;
 
-pub trait VotingPowerCalculator: Send {
+pub trait VotingPowerCalculator: Send + Sync {
     fn total_power_of(&self, validators: &ValidatorSet) -> u64;
     fn voting_power_in(
         &self,
@@ -31,9 +32,10 @@ impl VotingPowerCalculator for ProdVotingPowerCalculator {
     ) -> Result<u64, BoxError> {
         // NOTE: We don't know the validators that committed this block,
         //       so we have to check for each vote if its validator is already known.
-        let mut signed_power = 0_u64;
+        let votes = signed_header.signed_votes();
+        let mut checks = Vec::with_capacity(votes.len());
 
-        for vote in &signed_header.signed_votes() {
+        for vote in &votes {
             // Only count if this vote is from a known validator.
             // TODO: we still need to check that we didn't see a vote from this validator twice ...
             let val_id = vote.validator_id();
@@ -42,21 +44,25 @@ impl VotingPowerCalculator for ProdVotingPowerCalculator {
                 None => continue,
             };
 
-            // check vote is valid from validator
-            let sign_bytes = vote.sign_bytes();
+            checks.push(SignatureCheck {
+                validator: val,
+                sign_bytes: vote.sign_bytes(),
+                signature: vote.signature(),
+            });
+        }
 
-            if !val.verify_signature(&sign_bytes, vote.signature()) {
-                bail!(VerificationError::ImplementationSpecific(format!(
-                    "Couldn't verify signature {:?} with validator {:?} on sign_bytes {:?}",
-                    vote.signature(),
-                    val,
-                    sign_bytes,
-                )));
-            }
+        // check votes are valid from validators
+        if let Err(index) = verify_signatures(&checks) {
+            let check = &checks[index];
 
-            signed_power += val.power();
+            bail!(VerificationError::ImplementationSpecific(format!(
+                "Couldn't verify signature {:?} with validator {:?} on sign_bytes {:?}",
+                check.signature, check.validator, check.sign_bytes,
+            )));
         }
 
+        let signed_power = checks.iter().map(|check| check.validator.power()).sum();
+
         Ok(signed_power)
     }
 }
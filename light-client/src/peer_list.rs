@@ -1,19 +1,157 @@
 use crate::{
     bail,
     errors::{Error, ErrorKind},
+    peer_score::{PeerEvent, PeerScore, ScoringParams},
+    state::VerificationTrace,
+    store::VerifiedStatus,
     supervisor::Instance,
-    types::PeerId,
+    types::{Height, PeerId, Time},
 };
 
 use contracts::pre;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::{Duration, Instant};
 
-/// A mapping from PeerIds to Light Client instances.
-/// Keeps track of which peer is deemed the primary peer.
+/// Default amount of time a removed witness is kept in quarantine
+/// before it is considered again.
+pub const DEFAULT_QUARANTINE_PERIOD: Duration = Duration::from_secs(60);
+
+/// A witness which can be selected as the new primary.
 #[derive(Debug)]
+pub struct Candidate<'a> {
+    /// The peer id of the witness
+    pub peer_id: PeerId,
+    /// The light client instance of the witness
+    pub instance: &'a Instance,
+    /// The current penalty of the witness, see `PeerScore`
+    pub penalty: f64,
+    /// The average latency of the witness, if known
+    pub latency: Option<Duration>,
+}
+
+/// A policy for selecting a new primary among the available witnesses.
+pub trait PrimarySelector: Send + Sync {
+    /// Select the next primary among the given candidates, or return `None`
+    /// if none of them is suitable. The candidates are sorted by peer id.
+    fn select(&self, candidates: &[Candidate<'_>]) -> Option<PeerId>;
+}
+
+/// Compare the latency of two candidates, unknown latencies coming last.
+fn compare_latency(a: &Candidate<'_>, b: &Candidate<'_>) -> Ordering {
+    (a.latency.is_none(), a.latency).cmp(&(b.latency.is_none(), b.latency))
+}
+
+/// Selects the witness with the lowest penalty, and then the lowest latency.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LowestPenalty;
+
+impl PrimarySelector for LowestPenalty {
+    fn select(&self, candidates: &[Candidate<'_>]) -> Option<PeerId> {
+        candidates
+            .iter()
+            .min_by(|a, b| {
+                a.penalty
+                    .partial_cmp(&b.penalty)
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| compare_latency(a, b))
+            })
+            .map(|candidate| candidate.peer_id)
+    }
+}
+
+/// Selects the witness with the lowest average latency.
+/// Witnesses which have not been queried yet come last.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LowestLatency;
+
+impl PrimarySelector for LowestLatency {
+    fn select(&self, candidates: &[Candidate<'_>]) -> Option<PeerId> {
+        candidates
+            .iter()
+            .min_by(|a, b| compare_latency(a, b))
+            .map(|candidate| candidate.peer_id)
+    }
+}
+
+/// Selects the witness which reported the highest block so far.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct HighestHeight;
+
+impl HighestHeight {
+    fn highest_reported(instance: &Instance) -> Option<Height> {
+        VerifiedStatus::iter()
+            .iter()
+            .flat_map(|status| instance.state.light_store.highest(*status))
+            .map(|light_block| light_block.height())
+            .max()
+    }
+}
+
+impl PrimarySelector for HighestHeight {
+    fn select(&self, candidates: &[Candidate<'_>]) -> Option<PeerId> {
+        candidates
+            .iter()
+            .rev()
+            .max_by_key(|candidate| Self::highest_reported(candidate.instance))
+            .map(|candidate| candidate.peer_id)
+    }
+}
+
+/// A peer which has been removed from the list of witnesses,
+/// and which will not be considered again before `retry_after`.
+#[derive(Debug)]
+struct Quarantined {
+    instance: Instance,
+    retry_after: Instant,
+}
+
+/// A snapshot of a `PeerList`, which can be persisted and later restored,
+/// eg. across restarts. It does not include the light client instances themselves.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeerListSnapshot {
+    /// The primary
+    pub primary: PeerId,
+    /// The witnesses
+    pub witnesses: Vec<PeerId>,
+    /// The peers in quarantine, with the time after which they will be considered again
+    pub quarantined: Vec<(PeerId, Time)>,
+    /// The peers which have been removed from the list
+    pub removed: Vec<PeerId>,
+    /// The verification trace of each peer, including the ones in quarantine
+    pub verification_traces: BTreeMap<PeerId, VerificationTrace>,
+}
+
+/// A mapping from PeerIds to Light Client instances.
+/// Keeps track of which peer is deemed the primary peer,
+/// of which peers are in quarantine, and of the score of each peer.
+///
+/// The score of each peer is persisted in the light store of its instance,
+/// and restored from there when the peer is added to the list.
 pub struct PeerList {
     peers: HashMap<PeerId, Instance>,
+    quarantine: BTreeMap<PeerId, Quarantined>,
+    removed: BTreeSet<PeerId>,
+    scores: HashMap<PeerId, PeerScore>,
     primary: PeerId,
+    primary_selector: Box<dyn PrimarySelector>,
+    quarantine_period: Duration,
+    scoring_params: ScoringParams,
+}
+
+impl std::fmt::Debug for PeerList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PeerList")
+            .field("peers", &self.peers)
+            .field("quarantine", &self.quarantine)
+            .field("removed", &self.removed)
+            .field("scores", &self.scores)
+            .field("primary", &self.primary)
+            .field("quarantine_period", &self.quarantine_period)
+            .field("scoring_params", &self.scoring_params)
+            .finish()
+    }
 }
 
 impl PeerList {
@@ -42,6 +180,21 @@ impl PeerList {
         self.peers.get_mut(&self.primary)
     }
 
+    /// Get the peer id of the current primary.
+    pub fn primary_id(&self) -> PeerId {
+        self.primary
+    }
+
+    /// Get the peer ids of all the witnesses,
+    /// (ie. all peers which are not the primary).
+    pub fn witnesses_ids(&self) -> Vec<PeerId> {
+        self.peers
+            .keys()
+            .filter(|peer_id| peer_id != &&self.primary)
+            .copied()
+            .collect()
+    }
+
     /// Get a list of references to all the witnesses,
     /// (ie. all peers which are not the primary).
     pub fn witnesses(&self) -> Vec<&Instance> {
@@ -52,6 +205,34 @@ impl PeerList {
             .collect()
     }
 
+    /// Get mutable references to the instances of all the peers,
+    /// including the ones in quarantine.
+    pub fn all_instances_mut(&mut self) -> Vec<&mut Instance> {
+        self.peers
+            .values_mut()
+            .chain(self.quarantine.values_mut().map(|q| &mut q.instance))
+            .collect()
+    }
+
+    /// Get the peer ids of all the peers in quarantine.
+    pub fn quarantined_ids(&self) -> Vec<PeerId> {
+        self.quarantine.keys().copied().collect()
+    }
+
+    /// Add the given peer to the list of witnesses.
+    /// Overrides the previous instance for this peer, and
+    /// releases the peer from quarantine, if needed.
+    ///
+    /// ## Precondition
+    /// - The given peer id must not be the primary peer id.
+    #[pre(peer_id != self.primary)]
+    pub fn add_witness(&mut self, peer_id: PeerId, instance: Instance) {
+        self.quarantine.remove(&peer_id);
+        self.removed.remove(&peer_id);
+        self.restore_score(peer_id, &instance);
+        self.peers.insert(peer_id, instance);
+    }
+
     /// Remove the given peer from the list of witnesses.
     ///
     /// ## Precondition
@@ -61,27 +242,261 @@ impl PeerList {
         self.peers.remove(peer_id);
     }
 
-    /// Swap the primary for the next available witness, if any.
+    /// Remove the given peer, be it the primary, a witness or a peer in quarantine.
+    /// If the peer is the primary, it is first swapped for the next available witness.
     ///
     /// ## Errors
-    /// - If there are no witness left, returns `ErrorKind::NoWitnessLeft`.
-    pub fn swap_primary(&mut self) -> Result<(), Error> {
-        while let Some(peer_id) = self.peers.keys().next() {
-            if peer_id != &self.primary {
-                self.primary = *peer_id;
-                return Ok(());
+    /// - If the peer is the primary and there are no witness left,
+    ///   returns `ErrorKind::NoValidPeerLeft`.
+    pub fn remove_peer(&mut self, peer_id: &PeerId) -> Result<(), Error> {
+        if peer_id == &self.primary {
+            self.swap_primary()?;
+        }
+
+        self.peers.remove(peer_id);
+        self.quarantine.remove(peer_id);
+        self.scores.remove(peer_id);
+        self.removed.insert(*peer_id);
+
+        Ok(())
+    }
+
+    /// Get the score of the given peer, if any event has been recorded for it.
+    pub fn score(&self, peer_id: &PeerId) -> Option<&PeerScore> {
+        self.scores.get(peer_id)
+    }
+
+    /// Get the current penalty of the given peer, see `PeerScore`.
+    pub fn penalty(&self, peer_id: &PeerId) -> f64 {
+        self.scores
+            .get(peer_id)
+            .map(|score| score.penalty_at(Time::now(), self.scoring_params.half_life))
+            .unwrap_or_default()
+    }
+
+    /// Whether the penalty of the given peer is high enough for it to be evicted.
+    pub fn should_evict(&self, peer_id: &PeerId) -> bool {
+        self.penalty(peer_id) >= self.scoring_params.eviction_threshold
+    }
+
+    /// Record the given event in the score of the given peer,
+    /// and persist the updated score in the light store of the peer.
+    ///
+    /// ## Errors
+    /// - If the updated score could not be persisted, in which case it is still recorded in memory
+    pub fn record(&mut self, peer_id: PeerId, event: PeerEvent) -> Result<(), Error> {
+        let now = Time::now();
+
+        let score = self
+            .scores
+            .entry(peer_id)
+            .or_insert_with(|| PeerScore::new(now));
+
+        score.record(event, &self.scoring_params, now);
+
+        let instance = match self.peers.get_mut(&peer_id) {
+            Some(instance) => Some(instance),
+            None => self
+                .quarantine
+                .get_mut(&peer_id)
+                .map(|quarantined| &mut quarantined.instance),
+        };
+
+        if let Some(instance) = instance {
+            instance.state.light_store.set_peer_score(score.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Restore the score of the given peer from the light store of its instance, if any.
+    fn restore_score(&mut self, peer_id: PeerId, instance: &Instance) {
+        match instance.state.light_store.peer_score() {
+            Some(score) => self.scores.insert(peer_id, score),
+            None => self.scores.remove(&peer_id),
+        };
+    }
+
+    /// Move the given peer from the list of witnesses to the quarantine,
+    /// where it will stay for the quarantine period of this peer list.
+    ///
+    /// ## Precondition
+    /// - The given peer id must not be the primary peer id.
+    #[pre(peer_id != &self.primary)]
+    pub fn quarantine_witness(&mut self, peer_id: &PeerId) {
+        if let Some(instance) = self.peers.remove(peer_id) {
+            let retry_after = Instant::now() + self.quarantine_period;
+
+            self.quarantine.insert(
+                *peer_id,
+                Quarantined {
+                    instance,
+                    retry_after,
+                },
+            );
+        }
+    }
+
+    /// Move the peers whose quarantine period is over back to the list of witnesses.
+    ///
+    /// Returns the peer ids of the released peers.
+    pub fn release_quarantined(&mut self) -> Vec<PeerId> {
+        let now = Instant::now();
+
+        let released: Vec<PeerId> = self
+            .quarantine
+            .iter()
+            .filter(|(_, quarantined)| quarantined.retry_after <= now)
+            .map(|(peer_id, _)| *peer_id)
+            .collect();
+
+        for peer_id in &released {
+            if let Some(quarantined) = self.quarantine.remove(peer_id) {
+                self.peers.insert(*peer_id, quarantined.instance);
+            }
+        }
+
+        released
+    }
+
+    /// Take a snapshot of this peer list.
+    pub fn snapshot(&self) -> PeerListSnapshot {
+        let now = Time::now();
+        let instant_now = Instant::now();
+
+        let quarantined = self
+            .quarantine
+            .iter()
+            .map(|(peer_id, quarantined)| {
+                let remaining = quarantined
+                    .retry_after
+                    .saturating_duration_since(instant_now);
+                (*peer_id, now + remaining)
+            })
+            .collect();
+
+        let verification_traces = self
+            .peers
+            .iter()
+            .chain(
+                self.quarantine
+                    .iter()
+                    .map(|(peer_id, quarantined)| (peer_id, &quarantined.instance)),
+            )
+            .map(|(peer_id, instance)| (*peer_id, instance.state.verification_trace.clone()))
+            .collect();
+
+        PeerListSnapshot {
+            primary: self.primary,
+            witnesses: self.witnesses_ids(),
+            quarantined,
+            removed: self.removed.iter().copied().collect(),
+            verification_traces,
+        }
+    }
+
+    /// Restore the given snapshot into this peer list.
+    ///
+    /// The snapshot only applies to the peers of this list: peers of the snapshot
+    /// which are not in this list are ignored, while peers of this list which
+    /// are not in the snapshot are kept as witnesses.
+    ///
+    /// ## Errors
+    /// - If the primary of the snapshot has been removed from the list
+    ///   and there is no witness left, returns `ErrorKind::NoValidPeerLeft`.
+    pub fn restore(&mut self, snapshot: &PeerListSnapshot) -> Result<(), Error> {
+        for (peer_id, trace) in &snapshot.verification_traces {
+            if let Some(instance) = self.peers.get_mut(peer_id) {
+                instance.state.verification_trace = trace.clone();
             }
         }
 
-        bail!(ErrorKind::NoValidPeerLeft)
+        if self.peers.contains_key(&snapshot.primary) {
+            self.primary = snapshot.primary;
+        }
+
+        for peer_id in &snapshot.removed {
+            self.remove_peer(peer_id)?;
+        }
+
+        let now = Time::now();
+        let instant_now = Instant::now();
+
+        for (peer_id, retry_after) in &snapshot.quarantined {
+            if peer_id == &self.primary {
+                continue;
+            }
+
+            if let Some(instance) = self.peers.remove(peer_id) {
+                let remaining = retry_after.duration_since(now).unwrap_or_default();
+
+                self.quarantine.insert(
+                    *peer_id,
+                    Quarantined {
+                        instance,
+                        retry_after: instant_now + remaining,
+                    },
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Swap the primary for the witness selected by the primary selection policy, if any.
+    /// The previous primary is kept as a witness.
+    ///
+    /// ## Errors
+    /// - If there are no witness left, or if none of them is deemed
+    ///   suitable by the policy, returns `ErrorKind::NoValidPeerLeft`.
+    pub fn swap_primary(&mut self) -> Result<(), Error> {
+        let mut candidates: Vec<Candidate<'_>> = self
+            .peers
+            .iter()
+            .filter(|(peer_id, _)| peer_id != &&self.primary)
+            .map(|(peer_id, instance)| Candidate {
+                peer_id: *peer_id,
+                instance,
+                penalty: self.penalty(peer_id),
+                latency: self
+                    .scores
+                    .get(peer_id)
+                    .and_then(|score| score.latency)
+                    .or_else(|| instance.light_client.latency()),
+            })
+            .collect();
+
+        candidates.sort_by_key(|candidate| candidate.peer_id);
+
+        match self.primary_selector.select(&candidates) {
+            Some(peer_id) => {
+                self.primary = peer_id;
+                Ok(())
+            }
+            None => bail!(ErrorKind::NoValidPeerLeft),
+        }
     }
 }
 
 /// A builder of `PeerList` with a fluent API.
-#[derive(Default)]
 pub struct PeerListBuilder {
     primary: Option<PeerId>,
     peers: HashMap<PeerId, Instance>,
+    primary_selector: Box<dyn PrimarySelector>,
+    quarantine_period: Duration,
+    scoring_params: ScoringParams,
+}
+
+impl Default for PeerListBuilder {
+    fn default() -> Self {
+        Self {
+            primary: None,
+            peers: HashMap::new(),
+            primary_selector: Box::new(LowestPenalty),
+            quarantine_period: DEFAULT_QUARANTINE_PERIOD,
+            scoring_params: ScoringParams::default(),
+        }
+    }
 }
 
 impl PeerListBuilder {
@@ -99,6 +514,27 @@ impl PeerListBuilder {
         self
     }
 
+    /// Use the given policy to select a new primary when the current one fails.
+    /// Defaults to `LowestPenalty`.
+    pub fn primary_selector(mut self, primary_selector: impl PrimarySelector + 'static) -> Self {
+        self.primary_selector = Box::new(primary_selector);
+        self
+    }
+
+    /// Keep removed witnesses in quarantine for the given amount of time.
+    /// Defaults to `DEFAULT_QUARANTINE_PERIOD`.
+    pub fn quarantine_period(mut self, quarantine_period: Duration) -> Self {
+        self.quarantine_period = quarantine_period;
+        self
+    }
+
+    /// Score the peers with the given parameters.
+    /// Defaults to `ScoringParams::default()`.
+    pub fn scoring_params(mut self, scoring_params: ScoringParams) -> Self {
+        self.scoring_params = scoring_params;
+        self
+    }
+
     /// Builds the `PeerList`.
     ///
     /// ## Precondition
@@ -107,9 +543,24 @@ impl PeerListBuilder {
         self.primary.is_some() && self.peers.contains_key(self.primary.as_ref().unwrap())
     )]
     pub fn build(self) -> PeerList {
+        let scores = self
+            .peers
+            .iter()
+            .filter_map(|(peer_id, instance)| {
+                let score = instance.state.light_store.peer_score()?;
+                Some((*peer_id, score))
+            })
+            .collect();
+
         PeerList {
             primary: self.primary.unwrap(),
             peers: self.peers,
+            quarantine: BTreeMap::new(),
+            removed: BTreeSet::new(),
+            scores,
+            primary_selector: self.primary_selector,
+            quarantine_period: self.quarantine_period,
+            scoring_params: self.scoring_params,
         }
     }
 }
@@ -0,0 +1,125 @@
+//! Scoring of the peers of the light client, based on their past behavior.
+//!
+//! Each misbehavior of a peer adds a penalty to its score, which decays
+//! exponentially over time. The score of the peers is used to select a new primary,
+//! and to decide when a witness should be evicted from the peer list.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::types::Time;
+
+/// Something a peer did which affects its score.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PeerEvent {
+    /// The peer did not respond in time
+    Timeout,
+    /// The peer provided a light block which failed verification
+    InvalidBlock,
+    /// The peer has been deemed faulty during fork detection
+    Faulty,
+    /// The peer responded to a request within the given amount of time
+    Latency(Duration),
+}
+
+/// Parameters of the scoring of peers.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoringParams {
+    /// Penalty for not responding in time
+    pub timeout_penalty: f64,
+    /// Penalty for providing a light block which fails verification
+    pub invalid_block_penalty: f64,
+    /// Penalty for being deemed faulty during fork detection
+    pub faulty_penalty: f64,
+    /// Amount of time after which a penalty has decayed by half
+    pub half_life: Duration,
+    /// Penalty from which a witness is evicted from the peer list
+    pub eviction_threshold: f64,
+}
+
+impl Default for ScoringParams {
+    fn default() -> Self {
+        Self {
+            timeout_penalty: 1.0,
+            invalid_block_penalty: 5.0,
+            faulty_penalty: 10.0,
+            half_life: Duration::from_secs(10 * 60),
+            eviction_threshold: 3.0,
+        }
+    }
+}
+
+/// Weight of the latest sample in the moving average of the latency.
+const LATENCY_WEIGHT: f64 = 0.2;
+
+/// The score of a peer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeerScore {
+    /// Accumulated penalty as of `updated_at`. The lower, the better.
+    pub penalty: f64,
+    /// Number of timeouts so far
+    pub timeouts: u64,
+    /// Number of invalid light blocks so far
+    pub invalid_blocks: u64,
+    /// Number of faulty fork detection results so far
+    pub faulty: u64,
+    /// Moving average of the latency of the peer, if known
+    pub latency: Option<Duration>,
+    /// Time of the latest update of this score
+    pub updated_at: Time,
+}
+
+impl PeerScore {
+    /// A blank score, as of the given time.
+    pub fn new(now: Time) -> Self {
+        Self {
+            penalty: 0.0,
+            timeouts: 0,
+            invalid_blocks: 0,
+            faulty: 0,
+            latency: None,
+            updated_at: now,
+        }
+    }
+
+    /// The accumulated penalty, decayed as of the given time.
+    pub fn penalty_at(&self, now: Time, half_life: Duration) -> f64 {
+        let elapsed = now.duration_since(self.updated_at).unwrap_or_default();
+        let half_lives = elapsed.as_secs_f64() / half_life.as_secs_f64().max(f64::EPSILON);
+
+        self.penalty * 0.5_f64.powf(half_lives)
+    }
+
+    /// Record the given event at the given time.
+    pub fn record(&mut self, event: PeerEvent, params: &ScoringParams, now: Time) {
+        let penalty = self.penalty_at(now, params.half_life);
+
+        self.penalty = match event {
+            PeerEvent::Timeout => {
+                self.timeouts += 1;
+                penalty + params.timeout_penalty
+            }
+            PeerEvent::InvalidBlock => {
+                self.invalid_blocks += 1;
+                penalty + params.invalid_block_penalty
+            }
+            PeerEvent::Faulty => {
+                self.faulty += 1;
+                penalty + params.faulty_penalty
+            }
+            PeerEvent::Latency(latency) => {
+                let average = match self.latency {
+                    Some(average) => {
+                        average.mul_f64(1.0 - LATENCY_WEIGHT) + latency.mul_f64(LATENCY_WEIGHT)
+                    }
+                    None => latency,
+                };
+
+                self.latency = Some(average);
+                penalty
+            }
+        };
+
+        self.updated_at = now;
+    }
+}
@@ -7,11 +7,15 @@ use crate::{
     types::{Header, Height, LightBlock, SignedHeader, Time, TrustThreshold, ValidatorSet},
 };
 
+use composed::ExtraPredicate;
 use errors::VerificationError;
+use report::{Predicate, PredicateOutcome, VerificationReport, VotingPowerTally};
 use std::time::Duration;
 use tendermint::lite::ValidatorSet as _;
 
+pub mod composed;
 pub mod errors;
+pub mod report;
 
 /// Production predicates, using the default implementation
 /// of the `VerificationPredicates` trait.
@@ -25,7 +29,15 @@ impl VerificationPredicates for ProdPredicates {}
 ///
 /// This enables test implementations to only override a single method rather than
 /// have to re-define every predicate.
-pub trait VerificationPredicates: Send {
+///
+/// Additional predicates, eg. enforcing the local policy of a chain, can be checked
+/// on top of these by providing them via `extra_predicates`, see `ComposedPredicates`.
+pub trait VerificationPredicates: Send + Sync {
+    /// Additional predicates, checked in order after all the other ones. None by default.
+    fn extra_predicates(&self) -> &[ExtraPredicate] {
+        &[]
+    }
+
     fn validator_sets_match(&self, light_block: &LightBlock) -> Result<(), VerificationError> {
         ensure!(
             light_block.signed_header.header.validators_hash == light_block.validators.hash(),
@@ -216,6 +228,30 @@ pub trait VerificationPredicates: Send {
         Ok(())
     }
 
+    fn valid_hash_link(
+        &self,
+        untrusted_header: &Header,
+        trusted_header: &Header,
+        header_hasher: &dyn HeaderHasher,
+    ) -> Result<(), VerificationError> {
+        let last_block_id = trusted_header
+            .last_block_id
+            .as_ref()
+            .ok_or_else(|| VerificationError::MissingLastBlockId(trusted_header.height.into()))?;
+
+        let header_hash = header_hasher.hash(untrusted_header);
+
+        ensure!(
+            header_hash == last_block_id.hash,
+            VerificationError::InvalidHashLink {
+                header_hash,
+                last_block_id_hash: last_block_id.hash,
+            }
+        );
+
+        Ok(())
+    }
+
     fn valid_next_validator_set(
         &self,
         light_block: &LightBlock,
@@ -315,5 +351,206 @@ pub fn verify(
         voting_power_calculator,
     )?;
 
+    // Check the additional predicates, if any.
+    for predicate in vp.extra_predicates() {
+        predicate.check(untrusted, trusted, options)?;
+    }
+
+    Ok(())
+}
+
+/// Evaluate all the predicates checked by `verify` on the given light blocks,
+/// without stopping at the first one which does not hold, and report their outcome
+/// along with the voting power tallies and the signers which overlap with the
+/// trusted validators.
+///
+/// As with `verify`, the next validator set of the trusted block is checked against
+/// the untrusted validators only if the untrusted block is the very next block,
+/// and the validators overlap and height monotonicity are checked otherwise.
+pub fn explain(
+    vp: &dyn VerificationPredicates,
+    voting_power_calculator: &dyn VotingPowerCalculator,
+    commit_validator: &dyn CommitValidator,
+    header_hasher: &dyn HeaderHasher,
+    trusted: &LightBlock,
+    untrusted: &LightBlock,
+    options: &Options,
+) -> VerificationReport {
+    let mut predicates = Vec::new();
+    let mut record = |predicate: Predicate, result: Result<(), VerificationError>| {
+        predicates.push(PredicateOutcome::new(predicate, result))
+    };
+
+    record(
+        Predicate::IsWithinTrustPeriod,
+        vp.is_within_trust_period(
+            &trusted.signed_header.header,
+            options.trusting_period,
+            options.clock_drift,
+            options.now,
+        ),
+    );
+
+    record(
+        Predicate::ValidatorSetsMatch,
+        vp.validator_sets_match(&untrusted),
+    );
+
+    record(
+        Predicate::NextValidatorsMatch,
+        vp.next_validators_match(&untrusted),
+    );
+
+    record(
+        Predicate::HeaderMatchesCommit,
+        vp.header_matches_commit(&untrusted.signed_header, header_hasher),
+    );
+
+    record(
+        Predicate::ValidCommit,
+        vp.valid_commit(
+            &untrusted.signed_header,
+            &untrusted.validators,
+            commit_validator,
+        ),
+    );
+
+    record(
+        Predicate::IsMonotonicBftTime,
+        vp.is_monotonic_bft_time(
+            &untrusted.signed_header.header,
+            &trusted.signed_header.header,
+        ),
+    );
+
+    let trusted_next_height = trusted.height().checked_add(1).expect("height overflow");
+
+    if untrusted.height() == trusted_next_height {
+        record(
+            Predicate::ValidNextValidatorSet,
+            vp.valid_next_validator_set(&untrusted, trusted),
+        );
+    } else {
+        record(
+            Predicate::IsMonotonicHeight,
+            vp.is_monotonic_height(
+                &untrusted.signed_header.header,
+                &trusted.signed_header.header,
+            ),
+        );
+
+        record(
+            Predicate::HasSufficientValidatorsOverlap,
+            vp.has_sufficient_validators_overlap(
+                &untrusted.signed_header,
+                &trusted.next_validators,
+                &options.trust_threshold,
+                voting_power_calculator,
+            ),
+        );
+    }
+
+    record(
+        Predicate::HasSufficientSignersOverlap,
+        vp.has_sufficient_signers_overlap(
+            &untrusted.signed_header,
+            &untrusted.validators,
+            voting_power_calculator,
+        ),
+    );
+
+    for predicate in vp.extra_predicates() {
+        record(
+            Predicate::Extra(predicate.name().to_string()),
+            predicate.check(untrusted, trusted, options),
+        );
+    }
+
+    let validators_overlap = VotingPowerTally::compute(
+        &untrusted.signed_header,
+        &trusted.next_validators,
+        options.trust_threshold.numerator,
+        options.trust_threshold.denominator,
+        voting_power_calculator,
+    );
+
+    let commit_power = VotingPowerTally::compute(
+        &untrusted.signed_header,
+        &untrusted.validators,
+        2,
+        3,
+        voting_power_calculator,
+    );
+
+    let overlapping_signers = untrusted
+        .signed_header
+        .signed_votes()
+        .iter()
+        .map(|vote| vote.validator_id())
+        .filter(|id| trusted.next_validators.validator(*id).is_some())
+        .collect();
+
+    VerificationReport {
+        trusted_height: trusted.height(),
+        untrusted_height: untrusted.height(),
+        predicates,
+        validators_overlap,
+        commit_power,
+        overlapping_signers,
+        trust_threshold: options.trust_threshold,
+    }
+}
+
+/// Verify the given light block against the trusted light block at the next height,
+/// by following the hash link from the latter down to the former.
+///
+/// As hash links do not depend on the validator sets, only the block the backward
+/// verification starts from needs to be within the trusting period, which is
+/// checked by the light client itself.
+///
+/// The hash link only covers the untrusted header, so the validator sets and the commit
+/// of the untrusted block are checked against that header before it can be trusted.
+///
+/// - Ensure the untrusted block is the block right below the trusted block
+/// - Ensure the untrusted block is older than the trusted block
+/// - Ensure the hash of the untrusted header matches the `last_block_id` of the trusted header
+/// - Ensure the validator sets of the untrusted block match the hashes in its header
+/// - Ensure the commit of the untrusted block is for its header
+pub fn verify_backward(
+    vp: &dyn VerificationPredicates,
+    header_hasher: &dyn HeaderHasher,
+    trusted: &LightBlock,
+    untrusted: &LightBlock,
+) -> Result<(), VerificationError> {
+    let untrusted_next_height = untrusted.height().checked_add(1).expect("height overflow");
+
+    // Ensure the untrusted block is the block right below the trusted block
+    ensure!(
+        untrusted_next_height == trusted.height(),
+        VerificationError::NonIncreasingHeight {
+            got: trusted.height(),
+            expected: untrusted_next_height,
+        }
+    );
+
+    // Ensure the untrusted block is older than the trusted block,
+    // ie. that the trusted block is more recent than the untrusted one.
+    vp.is_monotonic_bft_time(
+        &trusted.signed_header.header,
+        &untrusted.signed_header.header,
+    )?;
+
+    // Ensure the untrusted header is the one the trusted header links to
+    vp.valid_hash_link(
+        &untrusted.signed_header.header,
+        &trusted.signed_header.header,
+        header_hasher,
+    )?;
+
+    // Ensure the validator sets and the commit are the ones of the now trusted header
+    vp.validator_sets_match(&untrusted)?;
+    vp.next_validators_match(&untrusted)?;
+    vp.header_matches_commit(&untrusted.signed_header, header_hasher)?;
+
     Ok(())
 }
@@ -0,0 +1,231 @@
+//! Composable predicate sets, which layer additional predicates on top of the
+//! production ones, eg. to enforce the local policy of a chain.
+
+use std::fmt;
+use std::time::Duration;
+
+use tendermint::public_key::{Algorithm, PublicKey};
+
+use crate::{ensure, light_client::Options, types::LightBlock};
+
+use super::{errors::VerificationError, VerificationPredicates};
+
+/// Signature of the functions which can be used as additional predicates.
+///
+/// Such a function is given the untrusted light block, the trusted light block
+/// it is verified against, and the verification options.
+pub type PredicateFn =
+    dyn Fn(&LightBlock, &LightBlock, &Options) -> Result<(), VerificationError> + Send + Sync;
+
+/// A named predicate, checked on top of the ones of `VerificationPredicates`.
+/// See `VerificationPredicates::extra_predicates`.
+pub struct ExtraPredicate {
+    name: String,
+    check: Box<PredicateFn>,
+}
+
+impl ExtraPredicate {
+    /// Wrap the given function into a predicate with the given name.
+    pub fn new(
+        name: impl Into<String>,
+        check: impl Fn(&LightBlock, &LightBlock, &Options) -> Result<(), VerificationError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            check: Box::new(check),
+        }
+    }
+
+    /// The name of this predicate.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Check this predicate on the given untrusted light block.
+    pub fn check(
+        &self,
+        untrusted: &LightBlock,
+        trusted: &LightBlock,
+        options: &Options,
+    ) -> Result<(), VerificationError> {
+        (self.check)(untrusted, trusted, options)
+    }
+
+    /// Ensure that no more than `max_gap` elapsed between two consecutive blocks on average,
+    /// from the trusted block up to the untrusted one, ie. that no more than `max_gap` times
+    /// their height difference elapsed between them.
+    ///
+    /// In sequential verification, where the blocks are adjacent, this bounds the gap between
+    /// every two consecutive blocks. When skipping, the gaps between the blocks in between are
+    /// unknown, so that only their average is bounded.
+    pub fn max_block_time_gap(max_gap: Duration) -> Self {
+        Self::new("max_block_time_gap", move |untrusted, trusted, _| {
+            // Blocks verified backward are not subject to this predicate
+            if untrusted.height() <= trusted.height() {
+                return Ok(());
+            }
+
+            let untrusted_time = untrusted.signed_header.header.time;
+            let trusted_time = trusted.signed_header.header.time;
+
+            // A block older than the trusted one is caught by `is_monotonic_bft_time`
+            let gap = match untrusted_time.duration_since(trusted_time) {
+                Ok(gap) => gap,
+                Err(_) => return Ok(()),
+            };
+
+            let blocks = untrusted.height() - trusted.height();
+            let max_total_gap = max_gap.as_nanos() * u128::from(blocks);
+
+            ensure!(
+                gap.as_nanos() <= max_total_gap,
+                VerificationError::BlockTimeGapTooLarge {
+                    gap,
+                    max_gap: duration_from_nanos(max_total_gap),
+                }
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Ensure that the validator set of the untrusted block counts at least `min` validators.
+    pub fn min_validator_count(min: usize) -> Self {
+        Self::new("min_validator_count", move |untrusted, _, _| {
+            let count = untrusted.validators.validators().len();
+
+            ensure!(
+                count >= min,
+                VerificationError::InsufficientValidatorCount { count, min }
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Ensure that all the validators and next validators of the untrusted block
+    /// use a key of one of the given algorithms, eg. only secp256k1 keys.
+    pub fn allowed_key_algorithms(algorithms: Vec<Algorithm>) -> Self {
+        Self::new("allowed_key_algorithms", move |untrusted, _, _| {
+            let validators = untrusted
+                .validators
+                .validators()
+                .iter()
+                .chain(untrusted.next_validators.validators());
+
+            for validator in validators {
+                let algorithm = key_algorithm(&validator.pub_key);
+
+                ensure!(
+                    algorithms.contains(&algorithm),
+                    VerificationError::DisallowedValidatorKey {
+                        validator: validator.address,
+                        algorithm: algorithm.to_string(),
+                    }
+                );
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl fmt::Debug for ExtraPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtraPredicate")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// Convert the given number of nanoseconds, which must fit in a `Duration`, into one.
+fn duration_from_nanos(nanos: u128) -> Duration {
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+    Duration::new(
+        (nanos / NANOS_PER_SEC) as u64,
+        (nanos % NANOS_PER_SEC) as u32,
+    )
+}
+
+fn key_algorithm(public_key: &PublicKey) -> Algorithm {
+    match public_key {
+        PublicKey::Ed25519(_) => Algorithm::Ed25519,
+        PublicKey::Secp256k1(_) => Algorithm::Secp256k1,
+    }
+}
+
+/// The production predicates, along with additional predicates which are checked
+/// after all the others, in the order in which they were added.
+///
+/// Construct with `ComposedPredicates::builder()`.
+#[derive(Debug, Default)]
+pub struct ComposedPredicates {
+    extra_predicates: Vec<ExtraPredicate>,
+}
+
+impl ComposedPredicates {
+    /// Create a builder, starting from the production predicates only.
+    pub fn builder() -> ComposedPredicatesBuilder {
+        ComposedPredicatesBuilder::default()
+    }
+}
+
+impl VerificationPredicates for ComposedPredicates {
+    fn extra_predicates(&self) -> &[ExtraPredicate] {
+        &self.extra_predicates
+    }
+}
+
+/// Builder of `ComposedPredicates`.
+#[derive(Debug, Default)]
+pub struct ComposedPredicatesBuilder {
+    extra_predicates: Vec<ExtraPredicate>,
+}
+
+impl ComposedPredicatesBuilder {
+    /// Add the given predicate.
+    pub fn predicate(mut self, predicate: ExtraPredicate) -> Self {
+        self.extra_predicates.push(predicate);
+        self
+    }
+
+    /// Add a predicate with the given name, checked by the given function.
+    ///
+    /// A function enforcing a policy which is not covered by another variant
+    /// of `VerificationError` can fail with `VerificationError::PolicyViolation`.
+    pub fn check(
+        self,
+        name: impl Into<String>,
+        check: impl Fn(&LightBlock, &LightBlock, &Options) -> Result<(), VerificationError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.predicate(ExtraPredicate::new(name, check))
+    }
+
+    /// Add the `ExtraPredicate::max_block_time_gap` predicate.
+    pub fn max_block_time_gap(self, max_gap: Duration) -> Self {
+        self.predicate(ExtraPredicate::max_block_time_gap(max_gap))
+    }
+
+    /// Add the `ExtraPredicate::min_validator_count` predicate.
+    pub fn min_validator_count(self, min: usize) -> Self {
+        self.predicate(ExtraPredicate::min_validator_count(min))
+    }
+
+    /// Add the `ExtraPredicate::allowed_key_algorithms` predicate.
+    pub fn allowed_key_algorithms(self, algorithms: Vec<Algorithm>) -> Self {
+        self.predicate(ExtraPredicate::allowed_key_algorithms(algorithms))
+    }
+
+    /// Build the predicate set.
+    pub fn build(self) -> ComposedPredicates {
+        ComposedPredicates {
+            extra_predicates: self.extra_predicates,
+        }
+    }
+}
@@ -2,6 +2,10 @@ use anomaly::{BoxError, Context};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use std::time::Duration;
+
+use tendermint::account;
+
 use crate::errors::ErrorExt;
 use crate::types::{Hash, Height, Time, TrustThreshold};
 
@@ -9,6 +13,15 @@ use crate::types::{Hash, Height, Time, TrustThreshold};
 /// when validating or verifying a light block.
 #[derive(Debug, Clone, Error, PartialEq, Serialize, Deserialize)]
 pub enum VerificationError {
+    #[error("block time gap too large: gap={gap:?} max_gap={max_gap:?}")]
+    BlockTimeGapTooLarge { gap: Duration, max_gap: Duration },
+
+    #[error("disallowed validator key: validator={validator} algorithm={algorithm}")]
+    DisallowedValidatorKey {
+        validator: account::Id,
+        algorithm: String,
+    },
+
     #[error("header from the future: header_time={header_time} now={now}")]
     HeaderFromTheFuture { header_time: Time, now: Time },
 
@@ -30,6 +43,9 @@ pub enum VerificationError {
     #[error("invalid commit power: total_power={total_power} signed_power={signed_power}")]
     InsufficientCommitPower { total_power: u64, signed_power: u64 },
 
+    #[error("insufficient validator count: count={count} min={min}")]
+    InsufficientValidatorCount { count: usize, min: usize },
+
     #[error("invalid commit: {0}")]
     InvalidCommit(String),
 
@@ -51,6 +67,17 @@ pub enum VerificationError {
         validators_hash: Hash,
     },
 
+    #[error(
+        "invalid hash link: header_hash={header_hash} last_block_id_hash={last_block_id_hash}"
+    )]
+    InvalidHashLink {
+        header_hash: Hash,
+        last_block_id_hash: Hash,
+    },
+
+    #[error("missing last block id: height={0}")]
+    MissingLastBlockId(Height),
+
     #[error("non increasing height: got={got} expected={expected}")]
     NonIncreasingHeight { got: Height, expected: Height },
 
@@ -62,6 +89,9 @@ pub enum VerificationError {
 
     #[error("not withing trust period: at={at} now={now}")]
     NotWithinTrustPeriod { at: Time, now: Time },
+
+    #[error("policy violation: policy={policy} reason={reason}")]
+    PolicyViolation { policy: String, reason: String },
 }
 
 impl VerificationError {
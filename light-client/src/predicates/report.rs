@@ -0,0 +1,136 @@
+//! Structured reports of the evaluation of the verification predicates,
+//! as produced by `predicates::explain`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    operations::VotingPowerCalculator,
+    types::{Height, SignedHeader, TrustThreshold, ValidatorSet},
+};
+use tendermint::account;
+
+use super::errors::VerificationError;
+
+/// Identifies one of the `VerificationPredicates`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Predicate {
+    IsWithinTrustPeriod,
+    ValidatorSetsMatch,
+    NextValidatorsMatch,
+    HeaderMatchesCommit,
+    ValidCommit,
+    IsMonotonicBftTime,
+    ValidNextValidatorSet,
+    IsMonotonicHeight,
+    HasSufficientValidatorsOverlap,
+    HasSufficientSignersOverlap,
+    /// One of the `VerificationPredicates::extra_predicates`, with the given name
+    Extra(String),
+}
+
+/// The outcome of the evaluation of a single predicate.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PredicateOutcome {
+    /// The evaluated predicate
+    pub predicate: Predicate,
+    /// Whether the predicate holds
+    pub passed: bool,
+    /// Why the predicate does not hold, if it does not
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<VerificationError>,
+}
+
+impl PredicateOutcome {
+    pub(crate) fn new(predicate: Predicate, result: Result<(), VerificationError>) -> Self {
+        Self {
+            predicate,
+            passed: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+/// The voting power which signed a commit, out of the total voting power of a
+/// validator set, along with the voting power required to pass a threshold.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VotingPowerTally {
+    /// Total voting power of the validator set
+    pub total_power: u64,
+    /// Voting power of the validators of the set which signed the commit
+    pub signed_power: u64,
+    /// Minimum signed voting power needed to pass the threshold
+    pub required_power: u64,
+}
+
+impl VotingPowerTally {
+    /// Tally the voting power of the given validators in the given signed header,
+    /// against the threshold `numerator / denominator`, which must be strictly exceeded.
+    ///
+    /// Returns `None` if the voting power cannot be computed, eg. because of an
+    /// invalid signature.
+    pub(crate) fn compute(
+        signed_header: &SignedHeader,
+        validators: &ValidatorSet,
+        numerator: u64,
+        denominator: u64,
+        calculator: &dyn VotingPowerCalculator,
+    ) -> Option<Self> {
+        let total_power = calculator.total_power_of(validators);
+        let signed_power = calculator.voting_power_in(signed_header, validators).ok()?;
+
+        Some(Self {
+            total_power,
+            signed_power,
+            required_power: total_power * numerator / denominator + 1,
+        })
+    }
+
+    /// Whether the signed voting power passes the threshold.
+    pub fn is_sufficient(&self) -> bool {
+        self.signed_power >= self.required_power
+    }
+}
+
+/// Report of the evaluation of all the predicates involved in the verification
+/// of an untrusted light block against a trusted one.
+///
+/// Unlike `predicates::verify`, which stops at the first failing predicate,
+/// every applicable predicate is evaluated.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    /// Height of the trusted light block
+    pub trusted_height: Height,
+    /// Height of the untrusted light block
+    pub untrusted_height: Height,
+    /// Outcome of each predicate, in the order in which `predicates::verify` evaluates them
+    pub predicates: Vec<PredicateOutcome>,
+    /// Voting power of the trusted next validators in the untrusted commit,
+    /// against the trust threshold, if it could be computed
+    pub validators_overlap: Option<VotingPowerTally>,
+    /// Voting power of the untrusted validators in the untrusted commit,
+    /// against 2/3 of their total voting power, if it could be computed
+    pub commit_power: Option<VotingPowerTally>,
+    /// Signers of the untrusted commit which are part of the trusted next validators
+    pub overlapping_signers: Vec<account::Id>,
+    /// Trust threshold the validators overlap is checked against
+    pub trust_threshold: TrustThreshold,
+}
+
+impl VerificationReport {
+    /// Whether all the predicates hold, ie. whether verification succeeds.
+    pub fn is_success(&self) -> bool {
+        self.predicates.iter().all(|outcome| outcome.passed)
+    }
+
+    /// The predicates which do not hold.
+    pub fn failures(&self) -> impl Iterator<Item = &PredicateOutcome> {
+        self.predicates.iter().filter(|outcome| !outcome.passed)
+    }
+
+    /// The error returned by `predicates::verify` for the same light blocks,
+    /// ie. the one of the first predicate which does not hold, if any.
+    pub fn first_error(&self) -> Option<&VerificationError> {
+        self.failures().find_map(|outcome| outcome.error.as_ref())
+    }
+}
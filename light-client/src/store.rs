@@ -3,12 +3,22 @@
 //! See the `memory` and `sled` modules for:
 //! - a transient, in-memory implementation for testing purposes
 //! - a persistent, on-disk, sled-backed implementation for production
+//!
+//! See the `pruning` module for removing the light blocks which are not needed anymore,
+//! and the `export` module for moving light blocks between stores.
 
-use crate::types::{Height, LightBlock};
+use crate::{
+    errors::Error,
+    peer_score::PeerScore,
+    types::{Height, LightBlock, Time},
+};
 
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 
+pub mod export;
 pub mod memory;
+pub mod pruning;
 pub mod sled;
 
 /// Verification status of a light block.
@@ -43,18 +53,38 @@ impl VerifiedStatus {
 ///
 /// ## Implements
 /// - [LCV-DIST-STORE.1]
-pub trait LightStore: std::fmt::Debug + Send {
+pub trait LightStore: std::fmt::Debug + Send + Sync {
     /// Get the light block at the given height with the given status, or return `None` otherwise.
     fn get(&self, height: Height, status: VerifiedStatus) -> Option<LightBlock>;
     /// Update the `status` of the given `light_block`.
-    fn update(&mut self, light_block: LightBlock, status: VerifiedStatus);
+    ///
+    /// The light block is atomically removed from the other statuses,
+    /// so that it never ends up with several or no status.
+    fn update(&mut self, light_block: LightBlock, status: VerifiedStatus) -> Result<(), Error>;
     /// Insert a new light block in the store with the given status.
     /// Overrides any other block with the same height and status.
-    fn insert(&mut self, light_block: LightBlock, status: VerifiedStatus);
+    fn insert(&mut self, light_block: LightBlock, status: VerifiedStatus) -> Result<(), Error>;
     /// Remove the light block with the given height and status, if any.
-    fn remove(&mut self, height: Height, status: VerifiedStatus);
+    fn remove(&mut self, height: Height, status: VerifiedStatus) -> Result<(), Error>;
     /// Get the highest light block with the given status.
     fn highest(&self, status: VerifiedStatus) -> Option<LightBlock>;
+    /// Get the lowest light block with the given status.
+    fn lowest(&self, status: VerifiedStatus) -> Option<LightBlock>;
+    /// Get the highest light block with the given status strictly below the given height.
+    fn highest_below(&self, height: Height, status: VerifiedStatus) -> Option<LightBlock>;
+    /// Get an iterator of the light blocks with the given status within the given range
+    /// of heights, by increasing height.
+    fn range(
+        &self,
+        heights: Range<Height>,
+        status: VerifiedStatus,
+    ) -> Box<dyn Iterator<Item = LightBlock>>;
+    /// Get the highest verified light block whose header time is strictly before the given time.
+    fn latest_trusted_before(&self, time: Time) -> Option<LightBlock>;
     /// Get an iterator of all light blocks with the given status.
     fn all(&self, status: VerifiedStatus) -> Box<dyn Iterator<Item = LightBlock>>;
+    /// Get the score of the peer whose light blocks are in this store, if any.
+    fn peer_score(&self) -> Option<PeerScore>;
+    /// Record the score of the peer whose light blocks are in this store.
+    fn set_peer_score(&mut self, score: PeerScore) -> Result<(), Error>;
 }
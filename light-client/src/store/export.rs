@@ -0,0 +1,85 @@
+//! Export and import of the content of a light store.
+//!
+//! A `LightStoreDump` does not depend on the layout of any given store,
+//! and is serialized with the same representation of light blocks as the RPC API.
+//! It can therefore be used to move light blocks between stores of different kinds,
+//! or between databases which use different versions of the `SledStore` layout.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ensure,
+    errors::{Error, ErrorKind},
+    peer_score::PeerScore,
+    store::{LightStore, VerifiedStatus},
+    types::LightBlock,
+};
+
+/// The version of the format of the dumps produced by `export`.
+pub const DUMP_VERSION: u32 = 1;
+
+/// A light block along with its verification status.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DumpEntry {
+    pub status: VerifiedStatus,
+    pub light_block: LightBlock,
+}
+
+/// The whole content of a light store.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LightStoreDump {
+    /// The version of the format of this dump
+    pub version: u32,
+    /// All the light blocks in the store, by status then by increasing height
+    pub light_blocks: Vec<DumpEntry>,
+    /// The score of the peer whose light blocks are in the store, if any
+    pub peer_score: Option<PeerScore>,
+}
+
+/// Dump the whole content of the given light store.
+pub fn export(light_store: &dyn LightStore) -> LightStoreDump {
+    let light_blocks = VerifiedStatus::iter()
+        .iter()
+        .flat_map(|status| {
+            light_store.all(*status).map(move |light_block| DumpEntry {
+                status: *status,
+                light_block,
+            })
+        })
+        .collect();
+
+    LightStoreDump {
+        version: DUMP_VERSION,
+        light_blocks,
+        peer_score: light_store.peer_score(),
+    }
+}
+
+/// Insert the content of the given dump into the given light store.
+///
+/// Returns the number of light blocks which have been imported.
+///
+/// ## Errors
+/// - If the dump was produced by a more recent version of the light client
+/// - If a light block cannot be inserted into the light store
+pub fn import(light_store: &mut dyn LightStore, dump: LightStoreDump) -> Result<usize, Error> {
+    ensure!(
+        dump.version <= DUMP_VERSION,
+        ErrorKind::UnsupportedSchemaVersion {
+            found: dump.version,
+            supported: DUMP_VERSION,
+        }
+    );
+
+    let imported = dump.light_blocks.len();
+
+    for entry in dump.light_blocks {
+        light_store.insert(entry.light_block, entry.status)?;
+    }
+
+    if let Some(score) = dump.peer_score {
+        light_store.set_peer_score(score)?;
+    }
+
+    Ok(imported)
+}
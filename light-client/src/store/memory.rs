@@ -1,10 +1,13 @@
 use crate::{
+    errors::Error,
+    peer_score::PeerScore,
     store::{LightStore, VerifiedStatus},
-    types::{Height, LightBlock},
+    types::{Height, LightBlock, Time},
 };
 
 use std::collections::btree_map::Entry::*;
 use std::collections::BTreeMap;
+use std::ops::Range;
 
 /// Internal entry for the memory store
 #[derive(Clone, Debug, PartialEq)]
@@ -26,12 +29,14 @@ impl StoreEntry {
 #[derive(Debug, Clone, Default)]
 pub struct MemoryStore {
     store: BTreeMap<Height, StoreEntry>,
+    peer_score: Option<PeerScore>,
 }
 
 impl MemoryStore {
     pub fn new() -> Self {
         Self {
             store: BTreeMap::new(),
+            peer_score: None,
         }
     }
 }
@@ -45,21 +50,25 @@ impl LightStore for MemoryStore {
             .map(|e| e.light_block)
     }
 
-    fn insert(&mut self, light_block: LightBlock, status: VerifiedStatus) {
+    fn insert(&mut self, light_block: LightBlock, status: VerifiedStatus) -> Result<(), Error> {
         self.store
             .insert(light_block.height(), StoreEntry::new(light_block, status));
+
+        Ok(())
     }
 
-    fn remove(&mut self, height: Height, status: VerifiedStatus) {
+    fn remove(&mut self, height: Height, status: VerifiedStatus) -> Result<(), Error> {
         if let Occupied(e) = self.store.entry(height) {
             if e.get().status == status {
                 e.remove_entry();
             }
         }
+
+        Ok(())
     }
 
-    fn update(&mut self, light_block: LightBlock, status: VerifiedStatus) {
-        self.insert(light_block, status);
+    fn update(&mut self, light_block: LightBlock, status: VerifiedStatus) -> Result<(), Error> {
+        self.insert(light_block, status)
     }
 
     fn highest(&self, status: VerifiedStatus) -> Option<LightBlock> {
@@ -70,6 +79,49 @@ impl LightStore for MemoryStore {
             .map(|(_, e)| e.light_block.clone())
     }
 
+    fn lowest(&self, status: VerifiedStatus) -> Option<LightBlock> {
+        self.store
+            .iter()
+            .find(|(_, e)| e.status == status)
+            .map(|(_, e)| e.light_block.clone())
+    }
+
+    fn highest_below(&self, height: Height, status: VerifiedStatus) -> Option<LightBlock> {
+        self.store
+            .range(..height)
+            .rev()
+            .find(|(_, e)| e.status == status)
+            .map(|(_, e)| e.light_block.clone())
+    }
+
+    fn range(
+        &self,
+        heights: Range<Height>,
+        status: VerifiedStatus,
+    ) -> Box<dyn Iterator<Item = LightBlock>> {
+        if heights.start >= heights.end {
+            return Box::new(std::iter::empty());
+        }
+
+        let light_blocks: Vec<_> = self
+            .store
+            .range(heights)
+            .filter(|(_, e)| e.status == status)
+            .map(|(_, e)| e.light_block.clone())
+            .collect();
+
+        Box::new(light_blocks.into_iter())
+    }
+
+    fn latest_trusted_before(&self, time: Time) -> Option<LightBlock> {
+        self.store
+            .iter()
+            .rev()
+            .filter(|(_, e)| e.status == VerifiedStatus::Verified)
+            .find(|(_, e)| e.light_block.signed_header.header.time < time)
+            .map(|(_, e)| e.light_block.clone())
+    }
+
     fn all(&self, status: VerifiedStatus) -> Box<dyn Iterator<Item = LightBlock>> {
         let light_blocks: Vec<_> = self
             .store
@@ -80,4 +132,14 @@ impl LightStore for MemoryStore {
 
         Box::new(light_blocks.into_iter())
     }
+
+    fn peer_score(&self) -> Option<PeerScore> {
+        self.peer_score.clone()
+    }
+
+    fn set_peer_score(&mut self, score: PeerScore) -> Result<(), Error> {
+        self.peer_score = Some(score);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,105 @@
+//! Pruning of the light blocks which are not needed anymore from a light store.
+//!
+//! Without pruning, a light store grows without bound, as neither the
+//! `MemoryStore` nor the `SledStore` ever delete anything on their own.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::{
+    contracts::is_within_trust_period,
+    errors::Error,
+    light_client::Options,
+    store::{LightStore, VerifiedStatus},
+    types::Height,
+};
+
+/// A policy deciding which light blocks to remove from a light store.
+///
+/// Whatever the policies, the highest verified light block is never removed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PruningPolicy {
+    /// Keep only the given number of highest verified light blocks
+    KeepLatestVerified(usize),
+    /// Remove the verified light blocks which are outside of the trusting period
+    OutsideTrustingPeriod,
+    /// Remove the unverified and failed light blocks whose header
+    /// is older than the given duration
+    PurgeNotVerifiedOlderThan(Duration),
+}
+
+/// Remove from the given light store the light blocks selected by the given policies,
+/// as of the trusting period and current time in the given options.
+///
+/// Returns the number of light blocks which have been removed.
+///
+/// ## Errors
+/// - If a light block cannot be removed from the light store
+pub fn prune(
+    light_store: &mut dyn LightStore,
+    policies: &[PruningPolicy],
+    options: &Options,
+) -> Result<usize, Error> {
+    let highest_trusted = light_store
+        .highest(VerifiedStatus::Verified)
+        .map(|lb| lb.height());
+
+    let mut removed = 0;
+
+    for policy in policies {
+        let to_remove = select(light_store, policy, options);
+
+        for (height, status) in to_remove {
+            if status == VerifiedStatus::Verified && Some(height) == highest_trusted {
+                continue;
+            }
+
+            light_store.remove(height, status)?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+/// The height and status of the light blocks selected by the given policy.
+fn select(
+    light_store: &dyn LightStore,
+    policy: &PruningPolicy,
+    options: &Options,
+) -> Vec<(Height, VerifiedStatus)> {
+    match policy {
+        PruningPolicy::KeepLatestVerified(count) => {
+            let mut heights = light_store
+                .all(VerifiedStatus::Verified)
+                .map(|lb| lb.height())
+                .collect::<Vec<_>>();
+
+            heights.sort_unstable_by(|a, b| b.cmp(a));
+
+            heights
+                .into_iter()
+                .skip(*count)
+                .map(|height| (height, VerifiedStatus::Verified))
+                .collect()
+        }
+        PruningPolicy::OutsideTrustingPeriod => light_store
+            .all(VerifiedStatus::Verified)
+            .filter(|lb| !is_within_trust_period(lb, options.trusting_period, options.now))
+            .map(|lb| (lb.height(), VerifiedStatus::Verified))
+            .collect(),
+        PruningPolicy::PurgeNotVerifiedOlderThan(age) => {
+            let cutoff = options.now - *age;
+
+            [VerifiedStatus::Unverified, VerifiedStatus::Failed]
+                .iter()
+                .flat_map(|status| {
+                    light_store
+                        .all(*status)
+                        .filter(move |lb| lb.signed_header.header.time < cutoff)
+                        .map(move |lb| (lb.height(), *status))
+                })
+                .collect()
+        }
+    }
+}
@@ -1,36 +1,121 @@
+pub mod migrations;
 pub mod utils;
 
 use crate::{
+    ensure,
+    errors::{Error, ErrorKind},
+    peer_score::PeerScore,
     store::sled::utils::*,
-    types::{Height, LightBlock},
+    types::{ChainId, Height, LightBlock, Time},
 };
 
 use super::{LightStore, VerifiedStatus};
 use ::sled::Db as SledDb;
+use std::ops::Range;
 
-const VERIFIED_PREFIX: &str = "light_store/verified";
-const UNVERIFIED_PREFIX: &str = "light_store/unverified";
-const FAILED_PREFIX: &str = "light_store/failed";
+/// Prefix of the keys of the light stores of all the chains.
+const CHAINS_PREFIX: &str = "light_store/chains/";
+
+const VERIFIED_PREFIX: &str = "verified";
+const UNVERIFIED_PREFIX: &str = "unverified";
+const FAILED_PREFIX: &str = "failed";
+const PEER_SCORE_PREFIX: &str = "peer_score";
+
+/// The prefix of the keys of the given kind in the light store of the given chain.
+///
+/// Chain identifiers cannot contain a `/`, so the prefixes of two different
+/// chains never overlap.
+fn chain_prefix(chain_id: &ChainId, kind: &str) -> String {
+    format!("{}{}/{}", CHAINS_PREFIX, chain_id, kind)
+}
 
 /// Persistent store backed by an on-disk `sled` database.
+///
+/// A single database can host the light stores of several chains,
+/// each of them only ever seeing the light blocks of its own chain.
 #[derive(Debug, Clone)]
 pub struct SledStore {
     db: SledDb,
+    chain_id: ChainId,
     verified_db: KeyValueDb<Height, LightBlock>,
     unverified_db: KeyValueDb<Height, LightBlock>,
     failed_db: KeyValueDb<Height, LightBlock>,
+    peer_score_db: SingleDb<PeerScore>,
 }
 
 impl SledStore {
-    pub fn new(db: SledDb) -> Self {
+    /// Open the light store of the given chain within the given database, upgrading
+    /// the layout of the database to the current schema version first if needed.
+    /// See the `migrations` module.
+    ///
+    /// ## Errors
+    /// - If the database was written by a more recent version of the light client
+    /// - If the layout of the database cannot be upgraded
+    pub fn open(db: SledDb, chain_id: ChainId) -> Result<Self, Error> {
+        migrations::migrate(&db)?;
+        Ok(Self::new(db, chain_id))
+    }
+
+    /// Create the light store of the given chain within the given database, which must
+    /// either be empty or already be at the current schema version.
+    /// Prefer `SledStore::open`.
+    pub fn new(db: SledDb, chain_id: ChainId) -> Self {
         Self {
             db,
-            verified_db: KeyValueDb::new(VERIFIED_PREFIX),
-            unverified_db: KeyValueDb::new(UNVERIFIED_PREFIX),
-            failed_db: KeyValueDb::new(FAILED_PREFIX),
+            chain_id,
+            verified_db: KeyValueDb::new(chain_prefix(&chain_id, VERIFIED_PREFIX)),
+            unverified_db: KeyValueDb::new(chain_prefix(&chain_id, UNVERIFIED_PREFIX)),
+            failed_db: KeyValueDb::new(chain_prefix(&chain_id, FAILED_PREFIX)),
+            peer_score_db: SingleDb::new(chain_prefix(&chain_id, PEER_SCORE_PREFIX)),
         }
     }
 
+    /// The chain whose light blocks are in this store.
+    pub fn chain_id(&self) -> ChainId {
+        self.chain_id
+    }
+
+    /// The chains which have a light store within the given database, in lexicographic order.
+    pub fn chains(db: &SledDb) -> Result<Vec<ChainId>, Error> {
+        let mut chains = Vec::new();
+        let mut start = CHAINS_PREFIX.as_bytes().to_vec();
+
+        while let Some(entry) = db.range(start..).keys().next() {
+            let key = entry.map_err(|e| ErrorKind::Store.context(e))?;
+            if !key.starts_with(CHAINS_PREFIX.as_bytes()) {
+                break;
+            }
+
+            let rest = &key[CHAINS_PREFIX.len()..];
+            let end = rest.iter().position(|b| *b == b'/').unwrap_or(rest.len());
+            let name =
+                std::str::from_utf8(&rest[..end]).map_err(|e| ErrorKind::Store.context(e))?;
+            let chain_id = name.parse().map_err(|e| ErrorKind::Store.context(e))?;
+
+            chains.push(chain_id);
+
+            // Skip over all the other keys of this chain, as `0` comes right after `/`
+            start = format!("{}{}0", CHAINS_PREFIX, name).into_bytes();
+        }
+
+        Ok(chains)
+    }
+
+    /// Ensure that the given light block belongs to the chain of this store.
+    fn check_chain_id(&self, light_block: &LightBlock) -> Result<(), Error> {
+        let chain_id = light_block.signed_header.header.chain_id;
+
+        ensure!(
+            chain_id == self.chain_id,
+            ErrorKind::ChainIdMismatch {
+                expected: self.chain_id,
+                found: chain_id,
+            }
+        );
+
+        Ok(())
+    }
+
     fn db(&self, status: VerifiedStatus) -> &KeyValueDb<Height, LightBlock> {
         match status {
             VerifiedStatus::Unverified => &self.unverified_db,
@@ -38,6 +123,26 @@ impl SledStore {
             VerifiedStatus::Failed => &self.failed_db,
         }
     }
+
+    /// Atomically move the given light block to the given status,
+    /// ie. remove it from the other statuses and insert it with the given status.
+    fn transition(&self, light_block: &LightBlock, status: VerifiedStatus) -> Result<(), Error> {
+        let height = light_block.height();
+
+        transaction(&self.db, |tx| {
+            let others = VerifiedStatus::iter()
+                .iter()
+                .filter(|other| **other != status);
+
+            for other in others {
+                self.db(*other).remove_tx(tx, &height)?;
+            }
+
+            self.db(status).insert_tx(tx, &height, light_block)?;
+
+            Ok(())
+        })
+    }
 }
 
 impl LightStore for SledStore {
@@ -45,33 +150,95 @@ impl LightStore for SledStore {
         self.db(status).get(&self.db, &height).ok().flatten()
     }
 
-    fn update(&mut self, light_block: LightBlock, status: VerifiedStatus) {
-        let height = &light_block.height();
-
-        for other in VerifiedStatus::iter() {
-            if status != *other {
-                self.db(*other).remove(&self.db, height).ok();
-            }
-        }
-
-        self.db(status).insert(&self.db, height, &light_block).ok();
+    fn update(&mut self, light_block: LightBlock, status: VerifiedStatus) -> Result<(), Error> {
+        self.check_chain_id(&light_block)?;
+        self.transition(&light_block, status)
     }
 
-    fn insert(&mut self, light_block: LightBlock, status: VerifiedStatus) {
+    fn insert(&mut self, light_block: LightBlock, status: VerifiedStatus) -> Result<(), Error> {
+        self.check_chain_id(&light_block)?;
         self.db(status)
             .insert(&self.db, &light_block.height(), &light_block)
-            .ok();
     }
 
-    fn remove(&mut self, height: Height, status: VerifiedStatus) {
-        self.db(status).remove(&self.db, &height).ok();
+    fn remove(&mut self, height: Height, status: VerifiedStatus) -> Result<(), Error> {
+        self.db(status).remove(&self.db, &height)
     }
 
     fn highest(&self, status: VerifiedStatus) -> Option<LightBlock> {
         self.db(status).iter(&self.db).next_back()
     }
 
+    fn lowest(&self, status: VerifiedStatus) -> Option<LightBlock> {
+        self.db(status).iter(&self.db).next()
+    }
+
+    fn highest_below(&self, height: Height, status: VerifiedStatus) -> Option<LightBlock> {
+        if height == 0 {
+            return None;
+        }
+
+        self.db(status)
+            .range(&self.db, 0..height)
+            .ok()
+            .and_then(|mut iter| iter.next_back())
+    }
+
+    fn range(
+        &self,
+        heights: Range<Height>,
+        status: VerifiedStatus,
+    ) -> Box<dyn Iterator<Item = LightBlock>> {
+        if heights.start >= heights.end {
+            return Box::new(std::iter::empty());
+        }
+
+        match self.db(status).range(&self.db, heights) {
+            Ok(iter) => Box::new(iter),
+            // TODO: Log/record the error
+            Err(_) => Box::new(std::iter::empty()),
+        }
+    }
+
+    /// As the header time of the verified light blocks increases with their height, the
+    /// latest one before the given time is found by bisecting over the heights, with one
+    /// range query per step, instead of scanning the verified light blocks.
+    fn latest_trusted_before(&self, time: Time) -> Option<LightBlock> {
+        let is_before = |lb: &LightBlock| lb.signed_header.header.time < time;
+
+        let mut latest = self.lowest(VerifiedStatus::Verified)?;
+        if !is_before(&latest) {
+            return None;
+        }
+
+        // The answer is the highest verified light block at or below some height in `low..=high`
+        let mut low = latest.height();
+        let mut high = self.highest(VerifiedStatus::Verified)?.height();
+
+        while low < high {
+            let middle = low + (high - low + 1) / 2;
+            let candidate = self.highest_below(middle + 1, VerifiedStatus::Verified)?;
+
+            if is_before(&candidate) {
+                low = middle;
+                latest = candidate;
+            } else {
+                high = middle - 1;
+            }
+        }
+
+        Some(latest)
+    }
+
     fn all(&self, status: VerifiedStatus) -> Box<dyn Iterator<Item = LightBlock>> {
         Box::new(self.db(status).iter(&self.db))
     }
+
+    fn peer_score(&self) -> Option<PeerScore> {
+        self.peer_score_db.get(&self.db).ok().flatten()
+    }
+
+    fn set_peer_score(&mut self, score: PeerScore) -> Result<(), Error> {
+        self.peer_score_db.set(&self.db, &score)
+    }
 }
@@ -0,0 +1,198 @@
+//! Versioning of the layout of the `SledStore` database.
+//!
+//! The version of the layout is recorded in the database under `SCHEMA_VERSION_KEY`.
+//! Opening a database with `SledStore::open` runs the migrations needed to
+//! bring it up to `SCHEMA_VERSION`, one version at a time.
+//!
+//! Any change to the layout of the database, or to the serialized representation of
+//! the values it contains (eg. `LightBlock`), must bump `SCHEMA_VERSION` and come with
+//! a migration from the previous version.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    bail,
+    errors::{Error, ErrorKind},
+    peer_score::PeerScore,
+    store::{
+        sled::{
+            utils::{transaction, KeyValueDb, SingleDb},
+            SledStore,
+        },
+        VerifiedStatus,
+    },
+    types::{Height, LightBlock},
+};
+
+/// The version of the database layout written by this version of the light client.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Key at which the version of the database layout is stored.
+pub const SCHEMA_VERSION_KEY: &str = "light_store/schema_version";
+
+/// Common prefix of all the keys written by the `SledStore`.
+const LIGHT_STORE_PREFIX: &[u8] = b"light_store/";
+
+/// A migration of the database from one version of the layout to the next one.
+#[derive(Copy, Clone, Debug)]
+pub struct Migration {
+    /// The version this migration upgrades from, to version `from + 1`
+    pub from: u32,
+    /// A short description of what this migration does
+    pub description: &'static str,
+    /// Upgrade the layout of the given database
+    pub run: fn(&sled::Db) -> Result<(), Error>,
+}
+
+/// The migrations needed to bring any database up to `SCHEMA_VERSION`.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "namespace the light blocks and the peer score by chain",
+    run: namespace_by_chain,
+}];
+
+/// The version of the layout of the databases created before the schema version was recorded.
+pub const UNVERSIONED_SCHEMA_VERSION: u32 = 1;
+
+/// In version 1, the light blocks of a single chain were stored at these prefixes.
+const V1_PREFIXES: &[(VerifiedStatus, &str)] = &[
+    (VerifiedStatus::Verified, "light_store/verified"),
+    (VerifiedStatus::Unverified, "light_store/unverified"),
+    (VerifiedStatus::Failed, "light_store/failed"),
+];
+
+/// In version 1, the peer score was stored at this prefix.
+const V1_PEER_SCORE_PREFIX: &str = "light_store/peer_score";
+
+/// Move the light blocks to the light store of their chain, in a single transaction.
+///
+/// The peer score is moved to the light store of every chain found,
+/// and dropped if the database does not contain any light block.
+///
+/// Fails without moving anything if one of the light blocks cannot be decoded.
+fn namespace_by_chain(db: &sled::Db) -> Result<(), Error> {
+    let legacy_blocks = V1_PREFIXES
+        .iter()
+        .flat_map(|(status, prefix)| {
+            KeyValueDb::<Height, LightBlock>::new(*prefix)
+                .try_iter(db)
+                .map(move |light_block| light_block.map(|lb| (*prefix, *status, lb)))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let chains = legacy_blocks
+        .iter()
+        .map(|(_, _, light_block)| light_block.signed_header.header.chain_id)
+        .collect::<BTreeSet<_>>();
+
+    let legacy_peer_score = SingleDb::<PeerScore>::new(V1_PEER_SCORE_PREFIX);
+    let peer_score = legacy_peer_score.get(db)?;
+
+    transaction(db, |tx| {
+        for (prefix, status, light_block) in &legacy_blocks {
+            let chain_id = light_block.signed_header.header.chain_id;
+
+            KeyValueDb::<Height, LightBlock>::new(*prefix).remove_tx(tx, &light_block.height())?;
+
+            SledStore::new(db.clone(), chain_id).db(*status).insert_tx(
+                tx,
+                &light_block.height(),
+                light_block,
+            )?;
+        }
+
+        if let Some(peer_score) = &peer_score {
+            for chain_id in &chains {
+                SledStore::new(db.clone(), *chain_id)
+                    .peer_score_db
+                    .set_tx(tx, peer_score)?;
+            }
+
+            legacy_peer_score.remove_tx(tx)?;
+        }
+
+        Ok(())
+    })
+}
+
+fn version_db() -> SingleDb<u32> {
+    SingleDb::new(SCHEMA_VERSION_KEY)
+}
+
+/// Get the version of the layout of the given database.
+///
+/// Returns `None` if the database is empty, and `UNVERSIONED_SCHEMA_VERSION` if
+/// it was created before the schema version was recorded.
+pub fn schema_version(db: &sled::Db) -> Result<Option<u32>, Error> {
+    if let Some(version) = version_db().get(db)? {
+        return Ok(Some(version));
+    }
+
+    match db.scan_prefix(LIGHT_STORE_PREFIX).next() {
+        None => Ok(None),
+        Some(Ok(_)) => Ok(Some(UNVERSIONED_SCHEMA_VERSION)),
+        Some(Err(e)) => Err(ErrorKind::Store.context(e).into()),
+    }
+}
+
+/// Bring the given database up to `SCHEMA_VERSION` with the built-in `MIGRATIONS`.
+///
+/// Returns the version of the database before the migrations, if it was not empty.
+///
+/// ## Errors
+/// - If the database was written by a more recent version of the light client
+/// - If one of the migrations fails
+pub fn migrate(db: &sled::Db) -> Result<Option<u32>, Error> {
+    migrate_to(db, MIGRATIONS, SCHEMA_VERSION)
+}
+
+/// Bring the given database up to the `target` version with the given migrations.
+///
+/// An empty database is directly marked as being at the `target` version.
+/// Otherwise, the migrations are run in order, and the new version is recorded
+/// after each of them, so that an interrupted upgrade resumes where it stopped.
+/// The version is left untouched by a failed migration.
+///
+/// Returns the version of the database before the migrations, if it was not empty.
+///
+/// ## Errors
+/// - If the database is more recent than the `target` version
+/// - If there is no migration from one of the intermediate versions
+/// - If one of the migrations fails
+pub fn migrate_to(
+    db: &sled::Db,
+    migrations: &[Migration],
+    target: u32,
+) -> Result<Option<u32>, Error> {
+    let initial = schema_version(db)?;
+    let mut version = initial.unwrap_or(target);
+
+    if version > target {
+        bail!(ErrorKind::UnsupportedSchemaVersion {
+            found: version,
+            supported: target,
+        });
+    }
+
+    while version < target {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.from == version)
+            .ok_or_else(|| {
+                ErrorKind::Store.context(format!("no migration from schema version {}", version))
+            })?;
+
+        (migration.run)(db)?;
+        version += 1;
+
+        version_db().set(db, &version)?;
+    }
+
+    if initial.is_none() {
+        version_db().set(db, &version)?;
+    }
+
+    db.flush().map_err(|e| ErrorKind::Store.context(e))?;
+
+    Ok(initial)
+}
@@ -3,10 +3,28 @@
 //! CBOR binary encoding.
 
 use serde::{de::DeserializeOwned, Serialize};
-use std::marker::PhantomData;
+use sled::transaction::{
+    ConflictableTransactionError, ConflictableTransactionResult, TransactionError,
+    TransactionalTree,
+};
+use std::{marker::PhantomData, ops::Range};
 
 use crate::errors::{Error, ErrorKind};
 
+/// Run the given closure atomically within a transaction on the given database:
+/// either all of its writes are applied, or none of them.
+///
+/// The closure may be run several times in case of conflicts with concurrent transactions.
+pub fn transaction<T>(
+    db: &sled::Db,
+    f: impl Fn(&TransactionalTree) -> ConflictableTransactionResult<T, Error>,
+) -> Result<T, Error> {
+    db.transaction(f).map_err(|e| match e {
+        TransactionError::Abort(e) => e,
+        TransactionError::Storage(e) => ErrorKind::Store.context(e).into(),
+    })
+}
+
 /// Provides a view over the database for storing a single value at the given prefix.
 pub fn single<V>(prefix: impl Into<Vec<u8>>) -> SingleDb<V> {
     SingleDb::new(prefix)
@@ -18,6 +36,7 @@ pub fn key_value<K, V>(prefix: impl Into<Vec<u8>>) -> KeyValueDb<K, V> {
 }
 
 /// Provides a view over the database for storing a single value at the given prefix.
+#[derive(Clone, Debug)]
 pub struct SingleDb<V>(KeyValueDb<(), V>);
 
 impl<V> SingleDb<V> {
@@ -37,6 +56,20 @@ where
     pub fn set(&self, db: &sled::Db, value: &V) -> Result<(), Error> {
         self.0.insert(&db, &(), &value)
     }
+
+    /// Transactional version of `set`, see `transaction`.
+    pub fn set_tx(
+        &self,
+        tx: &TransactionalTree,
+        value: &V,
+    ) -> ConflictableTransactionResult<(), Error> {
+        self.0.insert_tx(tx, &(), value)
+    }
+
+    /// Remove the value, if any, within a transaction, see `transaction`.
+    pub fn remove_tx(&self, tx: &TransactionalTree) -> ConflictableTransactionResult<(), Error> {
+        self.0.remove_tx(tx, &())
+    }
 }
 
 /// Provides a view over the database for storing key/value pairs at the given prefix.
@@ -107,6 +140,37 @@ where
         Ok(())
     }
 
+    /// Transactional version of `insert`, see `transaction`.
+    pub fn insert_tx(
+        &self,
+        tx: &TransactionalTree,
+        key: &K,
+        value: &V,
+    ) -> ConflictableTransactionResult<(), Error> {
+        let key_bytes = serde_cbor::to_vec(&key).map_err(|e| abort(ErrorKind::Store.context(e)))?;
+        let prefixed_key_bytes = self.prefixed_key(key_bytes);
+        let value_bytes =
+            serde_cbor::to_vec(&value).map_err(|e| abort(ErrorKind::Store.context(e)))?;
+
+        tx.insert(prefixed_key_bytes, value_bytes)?;
+
+        Ok(())
+    }
+
+    /// Transactional version of `remove`, see `transaction`.
+    pub fn remove_tx(
+        &self,
+        tx: &TransactionalTree,
+        key: &K,
+    ) -> ConflictableTransactionResult<(), Error> {
+        let key_bytes = serde_cbor::to_vec(&key).map_err(|e| abort(ErrorKind::Store.context(e)))?;
+        let prefixed_key_bytes = self.prefixed_key(key_bytes);
+
+        tx.remove(prefixed_key_bytes)?;
+
+        Ok(())
+    }
+
     pub fn remove(&self, db: &sled::Db, key: &K) -> Result<(), Error> {
         let key_bytes = serde_cbor::to_vec(&key).map_err(|e| ErrorKind::Store.context(e))?;
         let prefixed_key_bytes = self.prefixed_key(key_bytes);
@@ -117,14 +181,52 @@ where
         Ok(())
     }
 
+    /// Iterate over the values stored at this prefix, in the order of their keys.
+    ///
+    /// The values which cannot be read or decoded are skipped, see `try_iter`.
     pub fn iter(&self, db: &sled::Db) -> impl DoubleEndedIterator<Item = V> {
-        db.iter()
+        self.try_iter(db).flatten()
+    }
+
+    /// Iterate over the values stored at this prefix, in the order of their keys,
+    /// yielding an error for each value which cannot be read or decoded.
+    pub fn try_iter(&self, db: &sled::Db) -> impl DoubleEndedIterator<Item = Result<V, Error>> {
+        db.scan_prefix(&self.prefix).map(|entry| {
+            let (_, bytes) = entry.map_err(|e| ErrorKind::Store.context(e))?;
+            let value = serde_cbor::from_slice(&bytes).map_err(|e| ErrorKind::Store.context(e))?;
+            Ok(value)
+        })
+    }
+
+    /// Iterate over the values stored at this prefix whose key lies within the given range,
+    /// in the order of their keys.
+    ///
+    /// ## Note
+    /// The order of the keys is the lexicographic order of their CBOR encoding, which
+    /// matches the natural order of unsigned integers, eg. heights.
+    pub fn range(
+        &self,
+        db: &sled::Db,
+        range: Range<K>,
+    ) -> Result<impl DoubleEndedIterator<Item = V>, Error> {
+        let start = serde_cbor::to_vec(&range.start).map_err(|e| ErrorKind::Store.context(e))?;
+        let end = serde_cbor::to_vec(&range.end).map_err(|e| ErrorKind::Store.context(e))?;
+
+        let iter = db
+            .range(self.prefixed_key(start)..self.prefixed_key(end))
             .flatten()
             .map(|(_, v)| serde_cbor::from_slice(&v))
-            .flatten()
+            .flatten();
+
+        Ok(iter)
     }
 }
 
+/// Abort the current transaction with the given error.
+fn abort(e: impl Into<Error>) -> ConflictableTransactionError<Error> {
+    ConflictableTransactionError::Abort(e.into())
+}
+
 // TODO: The test below is currently disabled because it fails on CI as we don't have
 // access to `/tmp`. Need to figure out how to specify a proper temp dir.
 
@@ -1,23 +1,41 @@
 use crate::{
     bail,
     callback::Callback,
-    components::io::IoError,
-    errors::{Error, ErrorKind},
+    components::{
+        clock::Skew,
+        io::{IoError, Runtime},
+    },
+    errors::{self, Error, ErrorKind},
+    evidence::{self, EvidenceReporter},
     fork_detector::{Fork, ForkDetection, ForkDetector},
     light_client::LightClient,
-    peer_list::PeerList,
+    peer_list::{PeerList, PeerListSnapshot},
+    peer_score::PeerEvent,
     state::State,
-    store::VerifiedStatus,
-    types::{Height, LightBlock},
+    store::{
+        pruning::{self, PruningPolicy},
+        sled::utils::SingleDb,
+        VerifiedStatus,
+    },
+    types::{Height, LightBlock, PeerId, Time},
 };
 
 use contracts::pre;
 use crossbeam_channel as channel;
+use futures::{
+    channel::{mpsc, oneshot},
+    future::{self, Either},
+    StreamExt,
+};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 /// Type alias for readability
 pub type VerificationResult = Result<LightBlock, Error>;
 
-/// Events which are exchanged between the `Supervisor` and its `Handle`s.
+const PEER_LIST_SNAPSHOT_PREFIX: &str = "supervisor/peer_list";
+
+/// Events which are exchanged between the `Supervisor` and its `Handle`s or `AsyncHandle`s.
 #[derive(Debug)]
 pub enum Event {
     // Inputs
@@ -27,14 +45,72 @@ pub enum Event {
     VerifyToHighest(Callback<VerificationResult>),
     /// Verify to the given height, call the provided callback with result
     VerifyToTarget(Height, Callback<VerificationResult>),
+    /// Send all the subsequent output events to the given channel
+    Subscribe(mpsc::UnboundedSender<Event>),
+    /// Get the latest block trusted by the primary, call the provided callback with it
+    GetLatestTrusted(Callback<Option<LightBlock>>),
+    /// Get the current peers, call the provided callback with them
+    GetPeers(Callback<Peers>),
+    /// Get the verification trace of the primary for the given height,
+    /// call the provided callback with it
+    GetVerificationTrace(Height, Callback<Vec<LightBlock>>),
+    /// Add the given peer as a witness, call the provided callback once done
+    AddWitness(PeerId, Box<Instance>, Callback<()>),
+    /// Remove the given peer, call the provided callback with the result
+    RemovePeer(PeerId, Callback<Result<(), Error>>),
 
     // Outputs
     /// The supervisor has terminated
     Terminated,
     /// The verification has succeded
     VerificationSuccessed(LightBlock),
-    /// The verification has failed
+    /// The verification has failed.
+    /// The subscribers receive a duplicate of the error, see `errors::duplicate`.
     VerificationFailed(Error),
+    /// The primary has been swapped, from the first peer to the second one
+    PrimarySwapped(PeerId, PeerId),
+    /// The given witness has been put in quarantine for the given reason
+    WitnessRemoved(PeerId, ErrorKind),
+    /// The given witness has been released from quarantine
+    WitnessReleased(PeerId),
+    /// A light block higher than the previous latest trusted block is now trusted
+    NewTrustedBlock(LightBlock),
+    /// A fork has been detected with the given peers
+    ForkDetected(Vec<PeerId>),
+    /// The given number of light blocks have been pruned from the light stores
+    Pruned(usize),
+    /// The local clock is estimated to be skewed by more than the clock drift
+    ClockSkewed(Skew),
+    /// The score of the given peer could not be persisted in its light store.
+    /// The subscribers receive a duplicate of the error, see `errors::duplicate`.
+    PeerScoreNotPersisted(PeerId, Error),
+}
+
+/// The peers currently known to a `Supervisor`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Peers {
+    /// The current primary
+    pub primary: PeerId,
+    /// The current witnesses
+    pub witnesses: Vec<PeerId>,
+    /// The peers currently in quarantine
+    pub quarantined: Vec<PeerId>,
+}
+
+/// Pruning policies applied by a `Supervisor` to the light stores of its instances
+/// on a fixed interval.
+#[derive(Clone, Debug)]
+struct Pruning {
+    policies: Vec<PruningPolicy>,
+    interval: Duration,
+    next_run: Instant,
+}
+
+/// Estimation of the skew of the local clock, at most once per interval.
+#[derive(Clone, Debug)]
+struct SkewDetection {
+    interval: Duration,
+    next_run: Instant,
 }
 
 /// An light client `Instance` packages a `LightClient` together with its `State`.
@@ -61,12 +137,30 @@ impl Instance {
 /// and verified. The other instances are considered as witnesses
 /// which are consulted to perform fork detection.
 ///
-/// If primary verification fails, the primary client is removed and a witness
-/// is promoted to primary. If a witness is deemed faulty, then the witness is
-/// removed.
+/// If primary verification fails, the primary client is put in quarantine and a witness
+/// is promoted to primary, as per the primary selection policy of the peer list.
+/// If a witness is deemed faulty or does not respond in time, then its score is penalized,
+/// and the witness is put in quarantine once its penalty reaches the eviction threshold.
+/// Peers in quarantine are considered again once their quarantine period is over.
+/// If a fork is detected, evidence of the fork is reported to the
+/// primary and to the remaining witnesses.
+///
+/// Peers can be added and removed at runtime via a `Handle` or an `AsyncHandle`.
+///
+/// The state of the supervisor, ie. its peers, the primary choice, the peers in quarantine
+/// and the verification traces of all peers, can be persisted in a `sled` database with
+/// `Supervisor::persist_state`, and restored on startup with `Supervisor::restore_state`.
+/// The light blocks themselves are persisted by the light store of each instance,
+/// eg. a `SledStore`. The light stores can be pruned periodically while the supervisor
+/// is running, see `Supervisor::prune_periodically`.
 ///
 /// The supervisor is intended to be ran in its own thread, and queried
-/// via a `Handle`, sync- or asynchronously.
+/// via a `Handle`, sync- or asynchronously. Alternatively, it can be ran as a
+/// task within an existing `tokio` runtime, and queried via an `AsyncHandle`.
+///
+/// Both kinds of handles can subscribe to the output events of the supervisor,
+/// eg. to be notified when the primary is swapped, when a witness is removed,
+/// when a fork is detected, or when a verification completes.
 ///
 /// ## Example
 ///
@@ -92,17 +186,74 @@ impl Instance {
 /// }
 /// ```
 ///
-/// ## TODO
-/// - Report evidence for forks
+/// ## Async example
+///
+/// ```rust,ignore
+/// let mut supervisor: Supervisor = todo!();
+/// let mut handle = supervisor.async_handle();
+///
+/// // Spawn the supervisor as a task within the current runtime.
+/// tokio::spawn(supervisor.run_async());
+///
+/// loop {
+///     match handle.verify_to_highest().await {
+///         Ok(light_block) => {
+///             println!("[ info  ] synced to block {}", light_block.height());
+///         }
+///         Err(e) => {
+///             println!("[ error ] sync failed: {}", e);
+///         }
+///     }
+///
+///     tokio::time::delay_for(Duration::from_millis(800)).await;
+/// }
+/// ```
+///
+/// ## Auto-sync example
+///
+/// Rather than driving the supervisor in a loop, one can let it follow the head of
+/// its primary in the background, see the `auto_sync` module.
+///
+/// ```rust,ignore
+/// let mut supervisor: Supervisor = todo!();
+/// let handle = supervisor.async_handle();
+/// let mut events = supervisor.async_handle().subscribe();
+///
+/// tokio::spawn(supervisor.run_async());
+/// tokio::spawn(auto_sync(handle, AutoSyncConfig::interval(Duration::from_millis(800))));
+///
+/// while let Some(event) = events.next().await {
+///     if let Event::NewTrustedBlock(light_block) = event {
+///         println!("[ info  ] synced to block {}", light_block.height());
+///     }
+/// }
+/// ```
+///
+/// ## Note
+/// When ran within a runtime, the light client instances of the supervisor
+/// should fetch light blocks with an `AsyncIo` component,
+/// see `LightClient::new_async`.
 pub struct Supervisor {
     /// List of peers (primary + witnesses)
     peers: PeerList,
     /// An instance of the fork detector
     fork_detector: Box<dyn ForkDetector>,
+    /// Reporter of fork evidence
+    evidence_reporter: Box<dyn EvidenceReporter>,
     /// Channel through which to reply to `Handle`s
-    sender: channel::Sender<Event>,
+    sender: mpsc::UnboundedSender<Event>,
     /// Channel through which to receive events from the `Handle`s
-    receiver: channel::Receiver<Event>,
+    receiver: mpsc::UnboundedReceiver<Event>,
+    /// Channels through which to send output events to the subscribers
+    subscribers: Vec<mpsc::UnboundedSender<Event>>,
+    /// Database in which to persist the state of the supervisor, if any
+    state_db: Option<sled::Db>,
+    /// Pruning policies to apply periodically, if any
+    pruning: Option<Pruning>,
+    /// Periodic estimation of the skew of the local clock, if any
+    skew_detection: Option<SkewDetection>,
+    /// Runtime on which the synchronous methods run
+    runtime: Runtime,
 }
 
 impl std::fmt::Debug for Supervisor {
@@ -117,80 +268,337 @@ impl std::fmt::Debug for Supervisor {
 static_assertions::assert_impl_all!(Supervisor: Send);
 
 impl Supervisor {
-    /// Constructs a new supevisor from the given list of peers and fork detector instance.
-    pub fn new(peers: PeerList, fork_detector: impl ForkDetector + 'static) -> Self {
-        let (sender, receiver) = channel::unbounded::<Event>();
+    /// Constructs a new supevisor from the given list of peers, fork detector
+    /// and evidence reporter instances.
+    pub fn new(
+        peers: PeerList,
+        fork_detector: impl ForkDetector + 'static,
+        evidence_reporter: impl EvidenceReporter + 'static,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded::<Event>();
 
         Self {
             peers,
             sender,
             receiver,
             fork_detector: Box::new(fork_detector),
+            evidence_reporter: Box::new(evidence_reporter),
+            subscribers: Vec::new(),
+            state_db: None,
+            pruning: None,
+            skew_detection: None,
+            runtime: Runtime::default(),
+        }
+    }
+
+    /// Prune the light stores of all the instances with the given policies
+    /// every `interval`, while the supervisor is running.
+    ///
+    /// The subscribers are notified of each pruning with an `Event::Pruned`.
+    pub fn prune_periodically(&mut self, policies: Vec<PruningPolicy>, interval: Duration) {
+        self.pruning = Some(Pruning {
+            policies,
+            interval,
+            next_run: Instant::now() + interval,
+        });
+    }
+
+    /// Prune the light stores of all the instances, including the ones in quarantine,
+    /// with the given policies. See `pruning::prune` for details.
+    ///
+    /// Returns the number of light blocks which have been removed.
+    ///
+    /// ## Errors
+    /// - If a light block cannot be removed from one of the light stores
+    pub fn prune(&mut self, policies: &[PruningPolicy]) -> Result<usize, Error> {
+        self.peers
+            .all_instances_mut()
+            .into_iter()
+            .map(|instance| {
+                let options = instance
+                    .light_client
+                    .options
+                    .with_now(instance.light_client.now());
+
+                pruning::prune(instance.state.light_store.as_mut(), policies, &options)
+            })
+            .sum()
+    }
+
+    /// Before verifying, estimate the skew of the local clock against the latest headers
+    /// of the primary and of the witnesses, at most once every `interval`.
+    /// See `Supervisor::estimate_clock_skew` for details.
+    pub fn detect_clock_skew(&mut self, interval: Duration) {
+        self.skew_detection = Some(SkewDetection {
+            interval,
+            next_run: Instant::now(),
+        });
+    }
+
+    /// Fetch the latest header of the primary and of each witness, and record their
+    /// times in the clock of the primary, for it to estimate its skew.
+    /// See `DriftDetectingClock` for a clock which does so.
+    ///
+    /// The subscribers are notified with an `Event::ClockSkewed` if the clock of the
+    /// primary then deems itself skewed, see `Clock::is_skewed`.
+    ///
+    /// Returns the estimated skew, if any. There is none if no peer responded,
+    /// or if the clock of the primary does not estimate its skew.
+    pub async fn estimate_clock_skew(&mut self) -> Option<Skew> {
+        let primary = self.peers.primary()?;
+
+        let mut instances = vec![primary];
+        instances.extend(self.peers.witnesses());
+
+        // TODO: Log/record the errors
+        let header_times: Vec<Time> = future::join_all(
+            instances
+                .iter()
+                .map(|instance| instance.light_client.latest_header_time_async()),
+        )
+        .await
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+
+        let skew = primary.light_client.record_header_times(&header_times)?;
+
+        if primary.light_client.is_clock_skewed() {
+            self.notify(|| Event::ClockSkewed(skew));
+        }
+
+        Some(skew)
+    }
+
+    /// Run the periodic estimation of the clock skew, if it is due, and schedule the next one.
+    async fn estimate_clock_skew_if_due(&mut self) {
+        match &mut self.skew_detection {
+            Some(detection) if detection.next_run <= Instant::now() => {
+                detection.next_run = Instant::now() + detection.interval;
+            }
+            _ => return,
+        }
+
+        self.estimate_clock_skew().await;
+    }
+
+    /// Run the periodic pruning, if it is due, and schedule the next one.
+    fn prune_if_due(&mut self) {
+        let policies = match &mut self.pruning {
+            Some(pruning) if pruning.next_run <= Instant::now() => {
+                pruning.next_run = Instant::now() + pruning.interval;
+                pruning.policies.clone()
+            }
+            _ => return,
+        };
+
+        // TODO: Log/record the error
+        if let Ok(removed) = self.prune(&policies) {
+            self.notify(|| Event::Pruned(removed));
+        }
+    }
+
+    /// Wait for the next event from the handles, or for the next periodic pruning
+    /// to be due, in which case `None` is returned.
+    async fn next_event(&mut self) -> Option<Event> {
+        let next_run = match &self.pruning {
+            Some(pruning) => pruning.next_run,
+            None => return self.receiver.next().await,
+        };
+
+        let deadline = Box::pin(tokio::time::delay_until(next_run.into()));
+
+        match future::select(self.receiver.next(), deadline).await {
+            Either::Left((event, _)) => event,
+            Either::Right(_) => None,
+        }
+    }
+
+    /// Persist the state of the supervisor in the given database after each
+    /// verification and after each change to the peer list.
+    ///
+    /// The database can be the one used by the `SledStore` of an instance,
+    /// as the state of the supervisor is stored under its own prefix.
+    pub fn persist_state(&mut self, db: sled::Db) {
+        self.state_db = Some(db);
+        self.save_if_persistent();
+    }
+
+    /// Save the state of the supervisor in the given database.
+    pub fn save_state(&self, db: &sled::Db) -> Result<(), Error> {
+        let snapshot_db = SingleDb::new(PEER_LIST_SNAPSHOT_PREFIX);
+        snapshot_db.set(db, &self.peers.snapshot())
+    }
+
+    /// Restore the state of the supervisor previously saved in the given database, if any,
+    /// into the current peer list. See `PeerList::restore` for details.
+    ///
+    /// Returns whether there was any state to restore.
+    pub fn restore_state(&mut self, db: &sled::Db) -> Result<bool, Error> {
+        let snapshot_db = SingleDb::<PeerListSnapshot>::new(PEER_LIST_SNAPSHOT_PREFIX);
+
+        match snapshot_db.get(db)? {
+            Some(snapshot) => {
+                self.peers.restore(&snapshot)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Save the state of the supervisor, if it has been asked to persist it.
+    fn save_if_persistent(&self) {
+        if let Some(db) = &self.state_db {
+            // TODO: Log/record the error
+            let _ = self.save_state(db);
         }
     }
 
     /// Verify to the highest block.
     #[pre(self.peers.primary().is_some())]
     pub fn verify_to_highest(&mut self) -> VerificationResult {
-        self.verify(None)
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.verify(None))
     }
 
     /// Verify to the block at the given height.
     #[pre(self.peers.primary().is_some())]
     pub fn verify_to_target(&mut self, height: Height) -> VerificationResult {
-        self.verify(Some(height))
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.verify(Some(height)))
+    }
+
+    /// Verify either to the latest block (if `height == None`) or to a given block (if `height == Some(height)`),
+    /// and notify the subscribers of the outcome.
+    async fn verify(&mut self, height: Option<Height>) -> VerificationResult {
+        for peer in self.peers.release_quarantined() {
+            self.notify(|| Event::WitnessReleased(peer));
+        }
+
+        self.estimate_clock_skew_if_due().await;
+
+        let previously_trusted = self
+            .latest_trusted()
+            .map(|light_block| light_block.height());
+
+        let result = self.verify_with_primary(height).await;
+        self.save_if_persistent();
+
+        match &result {
+            Ok(light_block) => {
+                self.notify(|| Event::VerificationSuccessed(light_block.clone()));
+
+                if previously_trusted.map_or(true, |height| light_block.height() > height) {
+                    self.notify(|| Event::NewTrustedBlock(light_block.clone()));
+                }
+            }
+            Err(e) => self.notify(|| Event::VerificationFailed(errors::duplicate(e))),
+        }
+
+        result
     }
 
     /// Verify either to the latest block (if `height == None`) or to a given block (if `height == Some(height)`).
-    #[pre(self.peers.primary().is_some())]
-    fn verify(&mut self, height: Option<Height>) -> VerificationResult {
+    async fn verify_with_primary(&mut self, height: Option<Height>) -> VerificationResult {
         // While there is a primary peer left:
         while let Some(primary) = self.peers.primary_mut() {
+            // Get the highest trusted state before verification, which is the
+            // last block the primary and the witnesses are expected to agree on.
+            let trusted_state = primary.state.light_store.highest(VerifiedStatus::Verified);
+
             // Perform light client core verification for the given height (or highest).
             let verdict = match height {
-                None => primary.light_client.verify_to_highest(&mut primary.state),
-                Some(height) => primary
-                    .light_client
-                    .verify_to_target(height, &mut primary.state),
+                None => {
+                    primary
+                        .light_client
+                        .verify_to_highest_async(&mut primary.state)
+                        .await
+                }
+                Some(height) => {
+                    primary
+                        .light_client
+                        .verify_to_target_async(height, &mut primary.state)
+                        .await
+                }
             };
 
             match verdict {
                 // Verification succeeded, let's peform fork detection
                 Ok(light_block) => {
                     // SAFETY: There must be a latest trusted state otherwise verification would have failed.
-                    let trusted_state = primary
-                        .state
-                        .light_store
-                        .highest(VerifiedStatus::Verified)
-                        .unwrap();
+                    let trusted_state = trusted_state.unwrap();
+
+                    self.record_latency(self.peers.primary_id());
+
+                    // A block which is not higher than the trusted state was either trusted
+                    // already, or verified backward by following the hash links down from
+                    // a block which has already been checked against the witnesses.
+                    if light_block.height() <= trusted_state.height() {
+                        return Ok(light_block);
+                    }
 
                     // Perform fork detection with the highest verified block as the trusted state.
-                    let outcome = self.detect_forks(&light_block, &trusted_state)?;
+                    let outcome = self.detect_forks(&light_block, &trusted_state).await?;
 
                     match outcome {
                         // There was a fork or a faulty peer
                         Some(forks) => {
                             let mut forked = Vec::with_capacity(forks.len());
+                            let mut untrusted = Vec::new();
 
                             for fork in forks {
                                 match fork {
-                                    // An actual fork was detected, report evidence and record forked peer.
-                                    Fork::Forked(block) => {
-                                        self.report_evidence(&block);
-                                        forked.push(block.provider);
+                                    // An actual fork was detected, record the conflicting block.
+                                    Fork::Forked(block, _divergence) => {
+                                        forked.push(block);
                                     }
-                                    // A witness has been deemed faulty, remove it from the peer list.
-                                    Fork::Faulty(block, _error) => {
-                                        self.peers.remove_witness(&block.provider);
+                                    // A potential fork was detected, we cannot produce evidence
+                                    // for it, but we cannot trust the primary either.
+                                    Fork::LackOfTrust(block, _divergence, _error) => {
+                                        untrusted.push(block.provider);
                                         // TODO: Log/record the error
                                     }
+                                    // A witness has been deemed faulty, penalize it.
+                                    Fork::Faulty(block, error) => {
+                                        self.penalize_witness(
+                                            block.provider,
+                                            PeerEvent::Faulty,
+                                            error,
+                                        );
+                                    }
+                                    // A witness did not respond in time, it might be down so
+                                    // let's penalize it.
+                                    Fork::Timeout(peer, error) => {
+                                        self.penalize_witness(peer, PeerEvent::Timeout, error);
+                                    }
+                                    // A witness served an invalid response, penalize it.
+                                    Fork::InvalidResponse(peer, error) => {
+                                        self.penalize_witness(peer, PeerEvent::InvalidBlock, error);
+                                    }
                                 }
                             }
 
-                            if !forked.is_empty() {
+                            if !forked.is_empty() || !untrusted.is_empty() {
+                                let mut forked_peers: Vec<PeerId> =
+                                    forked.iter().map(|block| block.provider).collect();
+
+                                // Report evidence for each conflicting block
+                                for block in &forked {
+                                    self.report_evidence(block, &trusted_state, &forked_peers)
+                                        .await;
+                                }
+
+                                forked_peers.extend(untrusted);
+
+                                self.notify(|| Event::ForkDetected(forked_peers.clone()));
+
                                 // Fork detected, exiting
-                                bail!(ErrorKind::ForkDetected(forked))
+                                bail!(ErrorKind::ForkDetected(forked_peers))
                             }
+
+                            // Only faulty or unresponsive witnesses, which have been
+                            // penalized above, none of which conflicts with the primary.
+                            return Ok(light_block);
                         }
                         None => {
                             // No fork detected, exiting
@@ -199,11 +607,25 @@ impl Supervisor {
                         }
                     }
                 }
+                // The local clock cannot be trusted, which no other primary would help with
+                Err(err) if matches!(err.kind(), ErrorKind::ClockSkewed(_)) => return Err(err),
                 // Verification failed
-                Err(_err) => {
-                    // Swap primary, and continue with new primary, if there is any witness left.
+                Err(err) => {
+                    let old_primary = self.peers.primary_id();
+
+                    if let Some(event) = peer_event(err.kind()) {
+                        self.record_peer_event(old_primary, event);
+                    }
+
+                    // Swap primary, put the failed one in quarantine, and continue with
+                    // new primary, if there is any witness left.
                     self.peers.swap_primary()?;
+                    self.peers.quarantine_witness(&old_primary);
                     // TODO: Log/record error
+
+                    let new_primary = self.peers.primary_id();
+                    self.notify(|| Event::PrimarySwapped(old_primary, new_primary));
+
                     continue;
                 }
             }
@@ -212,14 +634,129 @@ impl Supervisor {
         bail!(ErrorKind::NoValidPeerLeft)
     }
 
-    /// Report the given light block as evidence of a fork.
-    fn report_evidence(&mut self, _light_block: &LightBlock) {
-        ()
+    /// Report the given conflicting light block as evidence of a fork to the primary
+    /// and to every witness which is not part of the given list of forked peers.
+    ///
+    /// The evidence is built against `common_block`, ie. the last block trusted
+    /// by both the primary and the witness which provided the conflicting block.
+    async fn report_evidence(
+        &mut self,
+        conflicting_block: &LightBlock,
+        common_block: &LightBlock,
+        forked_peers: &[PeerId],
+    ) {
+        let evidence = evidence::light_client_attack(conflicting_block, common_block);
+
+        let honest_witnesses = self
+            .peers
+            .witnesses_ids()
+            .into_iter()
+            .filter(|peer_id| !forked_peers.contains(peer_id));
+
+        let peers: Vec<PeerId> = std::iter::once(self.peers.primary_id())
+            .chain(honest_witnesses)
+            .collect();
+
+        for peer in peers {
+            // A peer failing to accept the evidence must not prevent
+            // reporting it to the remaining peers.
+            // TODO: Log/record the error
+            let _ = self.evidence_reporter.report(evidence.clone(), peer).await;
+        }
+    }
+
+    /// Record the given misbehavior of the given witness, and put the witness in
+    /// quarantine if its penalty is now high enough, notifying the subscribers
+    /// of the reason why.
+    fn penalize_witness(&mut self, peer: PeerId, event: PeerEvent, reason: ErrorKind) {
+        self.record_peer_event(peer, event);
+
+        if self.peers.should_evict(&peer) {
+            self.peers.quarantine_witness(&peer);
+            self.notify(|| Event::WitnessRemoved(peer, reason.clone()));
+        }
+    }
+
+    /// Record the latency of the latest successful request to the given peer,
+    /// unless it has already been recorded.
+    fn record_latency(&mut self, peer: PeerId) {
+        let latency = self
+            .peers
+            .get(&peer)
+            .and_then(|instance| instance.light_client.take_latency());
+
+        if let Some(latency) = latency {
+            self.record_peer_event(peer, PeerEvent::Latency(latency));
+        }
+    }
+
+    /// Record the given event in the score of the given peer, notifying the subscribers
+    /// if the updated score could not be persisted.
+    fn record_peer_event(&mut self, peer: PeerId, event: PeerEvent) {
+        if let Err(e) = self.peers.record(peer, event) {
+            self.notify(|| Event::PeerScoreNotPersisted(peer, errors::duplicate(&e)));
+        }
+    }
+
+    /// Send the event built by `make_event` to each of the subscribers,
+    /// and forget about the subscribers which have gone away.
+    fn notify(&mut self, make_event: impl Fn() -> Event) {
+        self.subscribers
+            .retain(|subscriber| subscriber.unbounded_send(make_event()).is_ok());
+    }
+
+    /// Get the latest block trusted by the primary, if any.
+    pub fn latest_trusted(&self) -> Option<LightBlock> {
+        self.peers
+            .primary()
+            .and_then(|primary| primary.state.light_store.highest(VerifiedStatus::Verified))
+    }
+
+    /// Get the current primary, witnesses and peers in quarantine.
+    pub fn peers(&self) -> Peers {
+        Peers {
+            primary: self.peers.primary_id(),
+            witnesses: self.peers.witnesses_ids(),
+            quarantined: self.peers.quarantined_ids(),
+        }
+    }
+
+    /// Add the given peer as a witness, releasing it from quarantine if needed.
+    ///
+    /// ## Precondition
+    /// - The given peer id must not be the primary peer id.
+    #[pre(peer_id != self.peers.primary_id())]
+    pub fn add_witness(&mut self, peer_id: PeerId, instance: Instance) {
+        self.peers.add_witness(peer_id, instance);
+        self.save_if_persistent();
+    }
+
+    /// Remove the given peer, be it the primary, a witness or a peer in quarantine.
+    /// If the peer is the primary, a new primary is selected first.
+    pub fn remove_peer(&mut self, peer_id: PeerId) -> Result<(), Error> {
+        let old_primary = self.peers.primary_id();
+        self.peers.remove_peer(&peer_id)?;
+        self.save_if_persistent();
+
+        let new_primary = self.peers.primary_id();
+        if new_primary != old_primary {
+            self.notify(|| Event::PrimarySwapped(old_primary, new_primary));
+        }
+
+        Ok(())
+    }
+
+    /// Get the blocks the primary needed to verify the block at the given height,
+    /// highest first.
+    pub fn verification_trace(&self, height: Height) -> Vec<LightBlock> {
+        self.peers
+            .primary()
+            .map(|primary| primary.state.get_trace(height))
+            .unwrap_or_default()
     }
 
     /// Perform fork detection with the given block and trusted state.
-    #[pre(self.peers.primary().is_some())]
-    fn detect_forks(
+    async fn detect_forks(
         &mut self,
         light_block: &LightBlock,
         trusted_state: &LightBlock,
@@ -228,23 +765,25 @@ impl Supervisor {
             bail!(ErrorKind::NoWitnesses);
         }
 
-        let result =
-            self.fork_detector
-                .detect_forks(light_block, &trusted_state, self.peers.witnesses());
+        let primary = self
+            .peers
+            .primary()
+            .ok_or_else(|| ErrorKind::NoValidPeerLeft)?;
+
+        let result = self
+            .fork_detector
+            .detect_forks(light_block, &trusted_state, primary, self.peers.witnesses())
+            .await;
+
+        // Only the witnesses which returned a block during this round have a latency to record.
+        for witness in self.peers.witnesses_ids() {
+            self.record_latency(witness);
+        }
 
         match result {
             Ok(ForkDetection::Detected(forks)) => Ok(Some(forks)),
             Ok(ForkDetection::NotDetected) => Ok(None),
-            Err(e) => match e.kind() {
-                // TODO: Clean this up
-                // Some RPC request timed out, this peer might be down so let's
-                // remove it from the witnesses, and bubble the error up.
-                ErrorKind::Io(IoError::Timeout(peer)) => {
-                    self.peers.remove_witness(peer);
-                    Err(e)
-                }
-                _ => Err(e),
-            },
+            Err(e) => Err(e),
         }
     }
 
@@ -253,12 +792,35 @@ impl Supervisor {
         Handle::new(self.sender.clone())
     }
 
+    /// Create a new async handle to this supervisor.
+    pub fn async_handle(&mut self) -> AsyncHandle {
+        AsyncHandle::new(self.sender.clone())
+    }
+
     /// Run the supervisor event loop in the same thread.
     ///
     /// This method should typically be called within a new thread with `std::thread::spawn`.
-    pub fn run(mut self) {
+    pub fn run(self) {
+        let runtime = self.runtime.clone();
+        runtime.block_on(self.run_async())
+    }
+
+    /// Async version of `run`.
+    ///
+    /// The returned future should typically be spawned as a task
+    /// within an existing runtime, eg. with `tokio::spawn`.
+    pub async fn run_async(mut self) {
         loop {
-            let event = self.receiver.recv().unwrap();
+            self.prune_if_due();
+
+            let event = match self.next_event().await {
+                Some(event) => event,
+                // Time for the next periodic pruning
+                None if self.pruning.is_some() => continue,
+                // All the handles and the supervisor's own sender have been dropped,
+                // which cannot happen while the supervisor is running.
+                None => unreachable!(),
+            };
 
             match event {
                 Event::Terminate(callback) => {
@@ -266,11 +828,35 @@ impl Supervisor {
                     return;
                 }
                 Event::VerifyToTarget(height, callback) => {
-                    let outcome = self.verify_to_target(height);
+                    let outcome = self.verify(Some(height)).await;
                     callback.call(outcome);
                 }
                 Event::VerifyToHighest(callback) => {
-                    let outcome = self.verify_to_highest();
+                    let outcome = self.verify(None).await;
+                    callback.call(outcome);
+                }
+                Event::Subscribe(subscriber) => {
+                    self.subscribers.push(subscriber);
+                }
+                Event::GetLatestTrusted(callback) => {
+                    callback.call(self.latest_trusted());
+                }
+                Event::GetPeers(callback) => {
+                    callback.call(self.peers());
+                }
+                Event::GetVerificationTrace(height, callback) => {
+                    callback.call(self.verification_trace(height));
+                }
+                Event::AddWitness(peer_id, instance, callback) => {
+                    if peer_id != self.peers.primary_id() {
+                        self.add_witness(peer_id, *instance);
+                    }
+                    // TODO: Log/record attempt to override the primary
+
+                    callback.call(());
+                }
+                Event::RemovePeer(peer_id, callback) => {
+                    let outcome = self.remove_peer(peer_id);
                     callback.call(outcome);
                 }
                 _ => {
@@ -284,13 +870,13 @@ impl Supervisor {
 /// A handle to a `Supervisor` which allows to communicate with
 /// the supervisor across thread boundaries via message passing.
 pub struct Handle {
-    sender: channel::Sender<Event>,
+    sender: mpsc::UnboundedSender<Event>,
 }
 
 impl Handle {
     /// Crate a new handle that sends events to the supervisor via
     /// the given channel. For internal use only.
-    pub fn new(sender: channel::Sender<Event>) -> Self {
+    pub fn new(sender: mpsc::UnboundedSender<Event>) -> Self {
         Self { sender }
     }
 
@@ -322,7 +908,7 @@ impl Handle {
         });
 
         let event = make_event(callback);
-        self.sender.send(event).unwrap();
+        self.sender.unbounded_send(event).unwrap();
 
         match receiver.recv().unwrap() {
             Event::VerificationSuccessed(header) => Ok(header),
@@ -331,6 +917,62 @@ impl Handle {
         }
     }
 
+    /// Subscribe to the output events of the supervisor, ie. all the events
+    /// listed under `Outputs` in `Event`, except for `Terminated`.
+    ///
+    /// The returned receiver is a `Stream`, which can be consumed from
+    /// synchronous code with `futures::executor::block_on_stream`.
+    pub fn subscribe(&mut self) -> mpsc::UnboundedReceiver<Event> {
+        let (sender, receiver) = mpsc::unbounded::<Event>();
+        self.sender
+            .unbounded_send(Event::Subscribe(sender))
+            .unwrap();
+        receiver
+    }
+
+    /// Get the latest block trusted by the primary, if any.
+    pub fn latest_trusted(&mut self) -> Option<LightBlock> {
+        self.query(Event::GetLatestTrusted)
+    }
+
+    /// Get the current primary and witnesses.
+    pub fn peers(&mut self) -> Peers {
+        self.query(Event::GetPeers)
+    }
+
+    /// Get the blocks the primary needed to verify the block at the given height,
+    /// highest first.
+    pub fn verification_trace(&mut self, height: Height) -> Vec<LightBlock> {
+        self.query(|callback| Event::GetVerificationTrace(height, callback))
+    }
+
+    /// Add the given peer as a witness, releasing it from quarantine if needed.
+    /// Has no effect if the peer is the current primary.
+    pub fn add_witness(&mut self, peer_id: PeerId, instance: Instance) {
+        self.query(|callback| Event::AddWitness(peer_id, Box::new(instance), callback))
+    }
+
+    /// Remove the given peer, be it the primary, a witness or a peer in quarantine.
+    /// If the peer is the primary, a new primary is selected first.
+    pub fn remove_peer(&mut self, peer_id: PeerId) -> Result<(), Error> {
+        self.query(|callback| Event::RemovePeer(peer_id, callback))
+    }
+
+    /// Send the event built by `make_event` to the supervisor,
+    /// and wait for the answer.
+    fn query<T: Send + 'static>(&mut self, make_event: impl FnOnce(Callback<T>) -> Event) -> T {
+        let (sender, receiver) = channel::bounded::<T>(1);
+
+        let callback = Callback::new(move |answer| {
+            sender.send(answer).unwrap();
+        });
+
+        let event = make_event(callback);
+        self.sender.unbounded_send(event).unwrap();
+
+        receiver.recv().unwrap()
+    }
+
     /// Async version of `verify_to_highest`.
     ///
     /// The given `callback` will be called asynchronously with the
@@ -340,7 +982,7 @@ impl Handle {
         callback: impl FnOnce(VerificationResult) -> () + Send + 'static,
     ) {
         let event = Event::VerifyToHighest(Callback::new(callback));
-        self.sender.send(event).unwrap();
+        self.sender.unbounded_send(event).unwrap();
     }
 
     /// Async version of `verify_to_target`.
@@ -353,7 +995,7 @@ impl Handle {
         callback: impl FnOnce(VerificationResult) -> () + Send + 'static,
     ) {
         let event = Event::VerifyToTarget(height, Callback::new(callback));
-        self.sender.send(event).unwrap();
+        self.sender.unbounded_send(event).unwrap();
     }
 
     /// Terminate the underlying supervisor.
@@ -364,7 +1006,9 @@ impl Handle {
             sender.send(Event::Terminated).unwrap();
         });
 
-        self.sender.send(Event::Terminate(callback)).unwrap();
+        self.sender
+            .unbounded_send(Event::Terminate(callback))
+            .unwrap();
 
         while let Ok(event) = receiver.recv() {
             match event {
@@ -374,3 +1018,131 @@ impl Handle {
         }
     }
 }
+
+/// A handle to a `Supervisor` which allows to communicate with the supervisor
+/// via message passing, without blocking the current thread.
+///
+/// Each request returns a future which resolves once the supervisor
+/// has processed the request.
+pub struct AsyncHandle {
+    sender: mpsc::UnboundedSender<Event>,
+}
+
+impl AsyncHandle {
+    /// Crate a new async handle that sends events to the supervisor via
+    /// the given channel. For internal use only.
+    pub fn new(sender: mpsc::UnboundedSender<Event>) -> Self {
+        Self { sender }
+    }
+
+    /// Verify to the highest block.
+    pub async fn verify_to_highest(&mut self) -> VerificationResult {
+        self.query(Event::VerifyToHighest).await
+    }
+
+    /// Verify to the block at the given height.
+    pub async fn verify_to_target(&mut self, height: Height) -> VerificationResult {
+        self.query(|callback| Event::VerifyToTarget(height, callback))
+            .await
+    }
+
+    /// Subscribe to the output events of the supervisor, ie. all the events
+    /// listed under `Outputs` in `Event`, except for `Terminated`.
+    pub fn subscribe(&mut self) -> mpsc::UnboundedReceiver<Event> {
+        let (sender, receiver) = mpsc::unbounded::<Event>();
+        self.sender
+            .unbounded_send(Event::Subscribe(sender))
+            .unwrap();
+        receiver
+    }
+
+    /// Get the latest block trusted by the primary, if any.
+    pub async fn latest_trusted(&mut self) -> Option<LightBlock> {
+        self.query(Event::GetLatestTrusted).await
+    }
+
+    /// Get the current primary and witnesses.
+    pub async fn peers(&mut self) -> Peers {
+        self.query(Event::GetPeers).await
+    }
+
+    /// Get the blocks the primary needed to verify the block at the given height,
+    /// highest first.
+    pub async fn verification_trace(&mut self, height: Height) -> Vec<LightBlock> {
+        self.query(|callback| Event::GetVerificationTrace(height, callback))
+            .await
+    }
+
+    /// Add the given peer as a witness, releasing it from quarantine if needed.
+    /// Has no effect if the peer is the current primary.
+    pub async fn add_witness(&mut self, peer_id: PeerId, instance: Instance) {
+        self.query(|callback| Event::AddWitness(peer_id, Box::new(instance), callback))
+            .await
+    }
+
+    /// Remove the given peer, be it the primary, a witness or a peer in quarantine.
+    /// If the peer is the primary, a new primary is selected first.
+    pub async fn remove_peer(&mut self, peer_id: PeerId) -> Result<(), Error> {
+        self.query(|callback| Event::RemovePeer(peer_id, callback))
+            .await
+    }
+
+    /// Verify to the highest block, or return `None` if the supervisor has terminated.
+    pub(crate) async fn try_verify_to_highest(&mut self) -> Option<VerificationResult> {
+        self.try_query(Event::VerifyToHighest).await
+    }
+
+    /// Send the event built by `make_event` to the supervisor,
+    /// and wait for the answer.
+    async fn query<T: Send + 'static>(
+        &mut self,
+        make_event: impl FnOnce(Callback<T>) -> Event + Send,
+    ) -> T {
+        self.try_query(make_event).await.unwrap()
+    }
+
+    /// Send the event built by `make_event` to the supervisor, and wait for the answer,
+    /// or return `None` if the supervisor has terminated.
+    async fn try_query<T: Send + 'static>(
+        &mut self,
+        make_event: impl FnOnce(Callback<T>) -> Event + Send,
+    ) -> Option<T> {
+        let (sender, receiver) = oneshot::channel::<T>();
+
+        let callback = Callback::new(move |answer| {
+            // The receiving end may have been dropped if the future was cancelled
+            let _ = sender.send(answer);
+        });
+
+        let event = make_event(callback);
+        self.sender.unbounded_send(event).ok()?;
+
+        receiver.await.ok()
+    }
+
+    /// Terminate the underlying supervisor.
+    pub async fn terminate(&mut self) {
+        let (sender, receiver) = oneshot::channel::<()>();
+
+        let callback = Callback::new(move |_| {
+            let _ = sender.send(());
+        });
+
+        self.sender
+            .unbounded_send(Event::Terminate(callback))
+            .unwrap();
+
+        receiver.await.unwrap()
+    }
+}
+
+/// The event to record in the score of a primary whose verification failed
+/// with the given error, if the error can be attributed to the primary.
+fn peer_event(e: &ErrorKind) -> Option<PeerEvent> {
+    match e {
+        ErrorKind::Io(IoError::Timeout(_)) => Some(PeerEvent::Timeout),
+        ErrorKind::Io(IoError::InvalidResponse(_)) => Some(PeerEvent::InvalidBlock),
+        ErrorKind::InvalidLightBlock(_) => Some(PeerEvent::InvalidBlock),
+        _ => None,
+    }
+}
@@ -1,11 +1,27 @@
 //! Utilities and datatypes for use in tests.
 
-use crate::types::{Hash, LightBlock, PeerId, SignedHeader, Time, TrustThreshold, ValidatorSet};
-
+use crate::{
+    components::{
+        clock::Clock,
+        io::{AtHeight, Io, IoError},
+    },
+    operations::{HeaderHasher, ProdHeaderHasher},
+    types::{Hash, Height, LightBlock, PeerId, SignedHeader, Time, TrustThreshold, ValidatorSet},
+};
+
+use contracts::contract_trait;
 use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use tendermint::block::Height as HeightStr;
 use tendermint::evidence::Duration as DurationStr;
+use tendermint_rpc as rpc;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct TestCases<LB> {
@@ -34,6 +50,8 @@ pub struct TestBisection<LB> {
     pub description: String,
     pub trust_options: TrustOptions,
     pub primary: Provider<LB>,
+    #[serde(default)]
+    pub witnesses: Vec<WitnessProvider<LB>>,
     pub height_to_verify: HeightStr,
     pub now: Time,
     pub expected_output: Option<String>,
@@ -46,6 +64,13 @@ pub struct Provider<LB> {
     pub lite_blocks: Vec<LB>,
 }
 
+#[derive(Deserialize, Clone, Debug)]
+pub struct WitnessProvider<LB> {
+    #[serde(rename = "type")]
+    pub provider_type: String,
+    pub value: Provider<LB>,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct TrustOptions {
     pub period: DurationStr,
@@ -69,6 +94,75 @@ impl Trusted {
     }
 }
 
+#[derive(Clone)]
+pub struct MockIo {
+    chain_id: String,
+    light_blocks: HashMap<Height, LightBlock>,
+    latest_height: Height,
+    fetches: Arc<AtomicUsize>,
+}
+
+impl MockIo {
+    pub fn new(chain_id: String, light_blocks: Vec<LightBlock>) -> Self {
+        let latest_height = light_blocks.iter().map(|lb| lb.height()).max().unwrap();
+
+        let light_blocks = light_blocks
+            .into_iter()
+            .map(|lb| (lb.height(), lb))
+            .collect();
+
+        Self {
+            chain_id,
+            light_blocks,
+            latest_height,
+            fetches: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The number of light blocks fetched so far, by this instance and all its clones.
+    pub fn fetch_count(&self) -> usize {
+        self.fetches.load(Ordering::SeqCst)
+    }
+}
+
+#[contract_trait]
+impl Io for MockIo {
+    fn fetch_light_block(&self, _peer: PeerId, height: AtHeight) -> Result<LightBlock, IoError> {
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+
+        let height = match height {
+            AtHeight::Highest => self.latest_height,
+            AtHeight::At(height) => height,
+        };
+
+        self.light_blocks
+            .get(&height)
+            .cloned()
+            .ok_or(rpc::Error::new((-32600).into(), None).into())
+    }
+
+    fn fetch_by_hash(&self, _peer: PeerId, hash: Hash) -> Result<LightBlock, IoError> {
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+
+        self.light_blocks
+            .values()
+            .find(|lb| ProdHeaderHasher.hash(&lb.signed_header.header) == hash)
+            .cloned()
+            .ok_or(rpc::Error::new((-32600).into(), None).into())
+    }
+}
+
+#[derive(Clone)]
+pub struct MockClock {
+    pub now: Time,
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Time {
+        self.now
+    }
+}
+
 // -----------------------------------------------------------------------------
 // Everything below is a temporary workaround for the lack of `provider` field
 // in the light blocks serialized in the JSON fixtures.
@@ -129,12 +223,22 @@ impl From<Provider<AnonLightBlock>> for Provider<LightBlock> {
     }
 }
 
+impl From<WitnessProvider<AnonLightBlock>> for WitnessProvider<LightBlock> {
+    fn from(w: WitnessProvider<AnonLightBlock>) -> Self {
+        Self {
+            provider_type: w.provider_type,
+            value: w.value.into(),
+        }
+    }
+}
+
 impl From<TestBisection<AnonLightBlock>> for TestBisection<LightBlock> {
     fn from(tb: TestBisection<AnonLightBlock>) -> Self {
         Self {
             description: tb.description,
             trust_options: tb.trust_options,
             primary: tb.primary.into(),
+            witnesses: tb.witnesses.into_iter().map(Into::into).collect(),
             height_to_verify: tb.height_to_verify,
             now: tb.now,
             expected_output: tb.expected_output,
@@ -17,6 +17,9 @@ pub use tendermint::{hash::Hash, lite::Height, time::Time};
 /// Peer ID (public key) of a full node
 pub type PeerId = tendermint::node::Id;
 
+/// Identifier of a blockchain
+pub type ChainId = tendermint::chain::Id;
+
 /// defines what fraction of the total voting power of a known
 /// and trusted validator set is sufficient for a commit to be
 /// accepted going forward.
@@ -0,0 +1,225 @@
+mod common;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::time::Duration;
+
+use tendermint_light_client::{
+    components::{
+        clock::{Clock, DriftDetectingClock, Skew},
+        io::{AtHeight, Io},
+        scheduler,
+        verifier::ProdVerifier,
+    },
+    errors::ErrorKind,
+    evidence::MemoryEvidenceReporter,
+    fork_detector::ProdForkDetector,
+    light_client::{LightClient, Options, VerificationMode},
+    peer_list::PeerList,
+    state::State,
+    store::{memory::MemoryStore, LightStore, VerifiedStatus},
+    supervisor::{Event, Instance, Supervisor},
+    tests::*,
+    types::{LightBlock, Time},
+};
+
+use common::read_bisection_test_case;
+
+const HAPPY_PATH: &str = "./tests/support/bisection/single_peer/happy_path.json";
+
+const CLOCK_DRIFT: Duration = Duration::from_secs(10);
+
+fn make_instance(tc: &TestBisection<LightBlock>, clock: impl Clock + 'static) -> Instance {
+    let peer = default_peer_id();
+    let io = MockIo::new(tc.primary.chain_id.clone(), tc.primary.lite_blocks.clone());
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let trusted_state = io
+        .fetch_light_block(peer, AtHeight::At(trusted_height))
+        .unwrap();
+
+    let mut light_store = MemoryStore::new();
+    light_store
+        .insert(trusted_state, VerifiedStatus::Verified)
+        .unwrap();
+
+    let state = State {
+        light_store: Box::new(light_store),
+        verification_trace: HashMap::new(),
+    };
+
+    let options = Options {
+        trust_threshold: tc.trust_options.trust_level,
+        trusting_period: tc.trust_options.period.into(),
+        clock_drift: CLOCK_DRIFT,
+        now: tc.now,
+        verification_mode: VerificationMode::Skipping,
+    };
+
+    let light_client = LightClient::new(
+        peer,
+        options,
+        clock,
+        scheduler::basic_bisecting_schedule,
+        ProdVerifier::default(),
+        io,
+    );
+
+    Instance::new(light_client, state)
+}
+
+fn latest_header_time(tc: &TestBisection<LightBlock>) -> Time {
+    tc.primary
+        .lite_blocks
+        .last()
+        .unwrap()
+        .signed_header
+        .header
+        .time
+}
+
+#[test]
+fn skew_is_estimated_against_the_median_header_time() {
+    let now = Time::now();
+    let minute = Duration::from_secs(60);
+
+    let header_times = [now + minute * 5, now - minute, now - minute * 2];
+    assert_eq!(
+        Skew::estimate(now, &header_times),
+        Some(Skew::Ahead(minute))
+    );
+
+    let header_times = [now + minute * 3, now - minute, now + minute * 2];
+    assert_eq!(
+        Skew::estimate(now, &header_times),
+        Some(Skew::Behind(minute * 2))
+    );
+
+    assert_eq!(Skew::estimate(now, &[]), None);
+}
+
+#[test]
+fn clocks_ahead_of_the_headers_may_exceed_the_clock_drift_by_the_max_lag() {
+    let hour = Duration::from_secs(3600);
+    let max_lag = Duration::from_secs(60);
+
+    assert!(Skew::Behind(hour).exceeds(CLOCK_DRIFT, max_lag));
+    assert!(Skew::Behind(CLOCK_DRIFT + max_lag).exceeds(CLOCK_DRIFT, max_lag));
+    assert!(!Skew::Behind(CLOCK_DRIFT).exceeds(CLOCK_DRIFT, max_lag));
+
+    assert!(Skew::Ahead(hour).exceeds(CLOCK_DRIFT, max_lag));
+    assert!(!Skew::Ahead(CLOCK_DRIFT + max_lag).exceeds(CLOCK_DRIFT, max_lag));
+}
+
+#[test]
+fn drift_detecting_clock_deems_clocks_too_far_ahead_skewed() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let hour = Duration::from_secs(3600);
+    let behind = tc.now - hour;
+
+    let clock = DriftDetectingClock::new(MockClock { now: tc.now }, CLOCK_DRIFT);
+    clock.record_header_times(&[behind]);
+    assert_eq!(clock.skew(), Some(Skew::Ahead(hour)));
+    assert!(clock.is_skewed());
+
+    // Unless the headers are expected to lag that much
+    let clock = clock.max_lag(hour * 2);
+    assert!(!clock.is_skewed());
+}
+
+#[test]
+fn drift_detecting_clock_refuses_verification_while_skewed() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let target_height = tc.height_to_verify.try_into().unwrap();
+
+    let clock =
+        DriftDetectingClock::new(MockClock { now: tc.now }, CLOCK_DRIFT).refuse_when_skewed(true);
+
+    let mut instance = make_instance(&tc, clock.clone());
+
+    // The clones of the clock share their estimate
+    let ahead = tc.now + Duration::from_secs(3600);
+    assert!(matches!(
+        clock.record_header_times(&[ahead]),
+        Some(Skew::Behind(_))
+    ));
+    assert!(instance.light_client.record_header_times(&[]).is_none());
+
+    let result = instance
+        .light_client
+        .verify_to_target(target_height, &mut instance.state);
+
+    match result {
+        Err(e) => assert!(matches!(e.kind(), ErrorKind::ClockSkewed(_))),
+        Ok(_) => panic!("verification should have been refused"),
+    }
+
+    // Verification resumes once the clock is no longer skewed
+    clock.record_header_times(&[tc.now]);
+    assert!(!clock.is_skewed());
+
+    let result = instance
+        .light_client
+        .verify_to_target(target_height, &mut instance.state);
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn drift_detecting_clock_only_warns_by_default() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let target_height = tc.height_to_verify.try_into().unwrap();
+
+    let clock = DriftDetectingClock::new(MockClock { now: tc.now }, CLOCK_DRIFT);
+    let mut instance = make_instance(&tc, clock.clone());
+
+    clock.record_header_times(&[tc.now + Duration::from_secs(3600)]);
+    assert!(clock.is_skewed());
+    assert!(clock.excessive_skew().is_none());
+
+    let result = instance
+        .light_client
+        .verify_to_target(target_height, &mut instance.state);
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn supervisor_notifies_subscribers_of_clock_skew() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let hour = Duration::from_secs(3600);
+
+    // The local clock is an hour behind the latest header of the peer
+    let now = latest_header_time(&tc) - hour;
+    let clock = DriftDetectingClock::new(MockClock { now }, CLOCK_DRIFT);
+
+    let peer_list = PeerList::builder()
+        .primary(default_peer_id(), make_instance(&tc, clock.clone()))
+        .build();
+
+    let mut supervisor = Supervisor::new(
+        peer_list,
+        ProdForkDetector::default(),
+        MemoryEvidenceReporter::new(),
+    );
+
+    supervisor.detect_clock_skew(Duration::from_secs(60));
+
+    let mut handle = supervisor.handle();
+    let events = handle.subscribe();
+
+    std::thread::spawn(|| supervisor.run());
+
+    // The outcome of the verification itself does not matter here
+    let _ = handle.verify_to_highest();
+
+    let skew = futures::executor::block_on_stream(events).find_map(|event| match event {
+        Event::ClockSkewed(skew) => Some(skew),
+        _ => None,
+    });
+
+    assert_eq!(skew, Some(Skew::Behind(hour)));
+    assert_eq!(clock.skew(), skew);
+
+    handle.terminate();
+}
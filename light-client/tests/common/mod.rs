@@ -0,0 +1,22 @@
+//! Helpers shared by the integration tests.
+
+// Each integration test only uses some of these helpers
+#![allow(dead_code)]
+
+use std::{fs, path::Path};
+
+use tendermint_light_client::{
+    tests::{AnonLightBlock, TestBisection},
+    types::{ChainId, LightBlock},
+};
+
+pub fn read_bisection_test_case(file_path: impl AsRef<Path>) -> TestBisection<LightBlock> {
+    let tc: TestBisection<AnonLightBlock> =
+        serde_json::from_str(&fs::read_to_string(file_path).unwrap()).unwrap();
+    tc.into()
+}
+
+/// The chain of the light blocks of the primary of the given test case.
+pub fn chain_id(tc: &TestBisection<LightBlock>) -> ChainId {
+    tc.primary.lite_blocks[0].signed_header.header.chain_id
+}
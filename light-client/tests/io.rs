@@ -0,0 +1,429 @@
+mod common;
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fs;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server, StatusCode};
+use serde_json::json;
+use tendermint::hash::{Algorithm, Hash};
+
+use tendermint_light_client::{
+    components::io::{
+        fetch_light_block_reusing, retry::RetryPolicy, AsyncIo, AtHeight, BlockingIo, Io, IoError,
+        ProdIo,
+    },
+    operations::{HeaderHasher, ProdHeaderHasher},
+    tests::*,
+    types::{ChainId, Height, LightBlock, PeerId, SignedHeader, ValidatorSet},
+};
+
+use common::{chain_id, read_bisection_test_case};
+
+const HAPPY_PATH: &str = "./tests/support/bisection/single_peer/happy_path.json";
+
+fn mock_io(tc: &TestBisection<LightBlock>) -> MockIo {
+    MockIo::new(tc.primary.chain_id.clone(), tc.primary.lite_blocks.clone())
+}
+
+fn block_on<F: Future>(f: F) -> F::Output {
+    let mut rt = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    rt.block_on(f)
+}
+
+#[test]
+fn fetch_light_block_skips_known_validator_sets() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let peer = default_peer_id();
+    let expected = tc.primary.lite_blocks[4].clone();
+
+    // Without any known validator set, the header and both validator sets are fetched
+    let io = mock_io(&tc);
+    let light_block = block_on(fetch_light_block_reusing(
+        &BlockingIo::new(io.clone()),
+        peer,
+        AtHeight::At(5),
+        &[],
+    ))
+    .unwrap();
+
+    assert_eq!(light_block, expected);
+    assert_eq!(io.fetch_count(), 3);
+
+    // Only the header is fetched when both validator sets are already known
+    let io = mock_io(&tc);
+    let known_validators = [
+        expected.next_validators.clone(),
+        expected.validators.clone(),
+    ];
+    let light_block = block_on(fetch_light_block_reusing(
+        &BlockingIo::new(io.clone()),
+        peer,
+        AtHeight::At(5),
+        &known_validators,
+    ))
+    .unwrap();
+
+    assert_eq!(light_block, expected);
+    assert_eq!(io.fetch_count(), 1);
+}
+
+#[test]
+fn fetch_by_hash() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let io = mock_io(&tc);
+    let peer = default_peer_id();
+
+    let expected = &tc.primary.lite_blocks[6];
+    let hash = ProdHeaderHasher.hash(&expected.signed_header.header);
+
+    assert_eq!(io.fetch_by_hash(peer, hash).as_ref(), Ok(expected));
+
+    let unknown = Hash::new(Algorithm::Sha256, &[0; 32]).unwrap();
+    assert!(io.fetch_by_hash(peer, unknown).is_err());
+}
+
+#[test]
+fn header_and_validator_set_operations_default_to_fetching_light_blocks() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let light_block = tc.primary.lite_blocks[2].clone();
+
+    let io = {
+        let light_block = light_block.clone();
+        move |_peer: PeerId, _height: AtHeight| -> Result<LightBlock, IoError> {
+            Ok(light_block.clone())
+        }
+    };
+
+    let peer = default_peer_id();
+
+    assert_eq!(
+        io.fetch_signed_header(peer, AtHeight::At(3)),
+        Ok(light_block.signed_header.clone())
+    );
+    assert_eq!(
+        io.fetch_validator_set(peer, 3),
+        Ok(light_block.validators.clone())
+    );
+
+    let hash = ProdHeaderHasher.hash(&light_block.signed_header.header);
+    assert!(io.fetch_by_hash(peer, hash).is_err());
+}
+
+const VALIDATORS_RESPONSE: &str = "../rpc/tests/support/validators.json";
+
+const RPC_ERROR_RESPONSE: &str = r#"{
+  "jsonrpc": "2.0",
+  "id": "",
+  "error": {
+    "code": -32603,
+    "message": "Internal error",
+    "data": "height 42 must be less than or equal to the current blockchain height 41"
+  }
+}"#;
+
+/// A local HTTP server, which serves the given responses in order,
+/// and then keeps serving the last one.
+struct MockServer {
+    addr: SocketAddr,
+    connections: Arc<AtomicUsize>,
+    requests: Arc<AtomicUsize>,
+}
+
+impl MockServer {
+    fn start(responses: Vec<(StatusCode, String)>) -> Self {
+        let responses = Arc::new(responses);
+        let connections = Arc::new(AtomicUsize::new(0));
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let (addr_tx, addr_rx) = mpsc::channel();
+
+        {
+            let connections = connections.clone();
+            let requests = requests.clone();
+
+            std::thread::spawn(move || {
+                let mut rt = tokio::runtime::Builder::new()
+                    .basic_scheduler()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+
+                rt.block_on(async move {
+                    let make_service = make_service_fn(move |_conn| {
+                        connections.fetch_add(1, Ordering::SeqCst);
+
+                        let responses = responses.clone();
+                        let requests = requests.clone();
+
+                        async move {
+                            Ok::<_, Infallible>(service_fn(move |_request| {
+                                let served = requests.fetch_add(1, Ordering::SeqCst);
+                                let (status, body) =
+                                    responses[served.min(responses.len() - 1)].clone();
+
+                                async move {
+                                    Ok::<_, Infallible>(
+                                        Response::builder()
+                                            .status(status)
+                                            .body(Body::from(body))
+                                            .unwrap(),
+                                    )
+                                }
+                            }))
+                        }
+                    });
+
+                    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+                    addr_tx.send(server.local_addr()).unwrap();
+                    server.await.unwrap();
+                });
+            });
+        }
+
+        Self {
+            addr: addr_rx.recv().unwrap(),
+            connections,
+            requests,
+        }
+    }
+
+    fn prod_io(&self, chain_id: ChainId, peer: PeerId, retry_policy: RetryPolicy) -> ProdIo {
+        let address = tendermint::net::Address::Tcp {
+            peer_id: None,
+            host: self.addr.ip().to_string(),
+            port: self.addr.port(),
+        };
+
+        let mut peer_map = HashMap::new();
+        peer_map.insert(peer, address);
+
+        ProdIo::new(chain_id, peer_map, Some(Duration::from_secs(5)))
+            .with_retry_policy(retry_policy)
+    }
+
+    fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+fn unavailable() -> (StatusCode, String) {
+    (StatusCode::SERVICE_UNAVAILABLE, "unavailable".to_string())
+}
+
+fn validators() -> (StatusCode, String) {
+    (
+        StatusCode::OK,
+        fs::read_to_string(VALIDATORS_RESPONSE).unwrap(),
+    )
+}
+
+fn rpc_error() -> (StatusCode, String) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        RPC_ERROR_RESPONSE.to_string(),
+    )
+}
+
+fn commit(signed_header: &SignedHeader) -> (StatusCode, String) {
+    let response = json!({
+        "jsonrpc": "2.0",
+        "id": "",
+        "result": { "signed_header": signed_header, "canonical": true },
+    });
+
+    (StatusCode::OK, response.to_string())
+}
+
+fn validator_set(height: Height, validator_set: &ValidatorSet) -> (StatusCode, String) {
+    let response = json!({
+        "jsonrpc": "2.0",
+        "id": "",
+        "result": {
+            "block_height": height.to_string(),
+            "validators": validator_set.validators(),
+        },
+    });
+
+    (StatusCode::OK, response.to_string())
+}
+
+/// The responses to the requests made when fetching the given light block.
+fn light_block_responses(light_block: &LightBlock) -> Vec<(StatusCode, String)> {
+    vec![
+        commit(&light_block.signed_header),
+        validator_set(light_block.height(), &light_block.validators),
+        validator_set(light_block.height() + 1, &light_block.next_validators),
+    ]
+}
+
+fn retry_policy(max_attempts: usize) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(10),
+        ..RetryPolicy::default()
+    }
+}
+
+#[test]
+fn prod_io_retries_transport_errors() {
+    let server = MockServer::start(vec![unavailable(), unavailable(), validators()]);
+    let chain_id = "test-chain-01".parse().unwrap();
+    let peer = default_peer_id();
+    let io = server.prod_io(chain_id, peer, retry_policy(3));
+
+    let validator_set = io.fetch_validator_set(peer, 42).unwrap();
+
+    assert_eq!(validator_set.validators().len(), 65);
+    assert_eq!(server.requests(), 3);
+}
+
+#[test]
+fn prod_io_gives_up_after_max_attempts() {
+    let server = MockServer::start(vec![unavailable()]);
+    let chain_id = "test-chain-01".parse().unwrap();
+    let peer = default_peer_id();
+    let io = server.prod_io(chain_id, peer, retry_policy(4));
+
+    let result = io.fetch_validator_set(peer, 42);
+
+    assert!(matches!(result, Err(IoError::Transport(_))));
+    assert_eq!(server.requests(), 4);
+}
+
+#[test]
+fn prod_io_does_not_retry_rpc_errors() {
+    let server = MockServer::start(vec![rpc_error(), validators()]);
+    let chain_id = "test-chain-01".parse().unwrap();
+    let peer = default_peer_id();
+    let io = server.prod_io(chain_id, peer, retry_policy(3));
+
+    let result = io.fetch_validator_set(peer, 42);
+
+    assert!(matches!(result, Err(IoError::IoError(_))));
+    assert_eq!(server.requests(), 1);
+}
+
+#[test]
+fn prod_io_reuses_connections() {
+    let server = MockServer::start(vec![validators()]);
+    let chain_id = "test-chain-01".parse().unwrap();
+    let peer = default_peer_id();
+    let io = server.prod_io(chain_id, peer, RetryPolicy::never());
+
+    block_on(async {
+        for _ in 0..3 {
+            AsyncIo::fetch_validator_set(&io, peer, 42).await.unwrap();
+        }
+    });
+
+    assert_eq!(server.requests(), 3);
+    assert_eq!(server.connections(), 1);
+}
+
+#[test]
+fn prod_io_reuses_connections_across_blocking_requests() {
+    let server = MockServer::start(vec![validators()]);
+    let chain_id = "test-chain-01".parse().unwrap();
+    let peer = default_peer_id();
+    let io = server.prod_io(chain_id, peer, RetryPolicy::never());
+
+    for _ in 0..3 {
+        io.fetch_validator_set(peer, 42).unwrap();
+    }
+
+    assert_eq!(server.requests(), 3);
+    assert_eq!(server.connections(), 1);
+}
+
+#[test]
+fn prod_io_accepts_consistent_light_blocks() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let expected = &tc.primary.lite_blocks[4];
+
+    let server = MockServer::start(light_block_responses(expected));
+    let peer = default_peer_id();
+    let io = server.prod_io(chain_id(&tc), peer, RetryPolicy::never());
+
+    let light_block = io.fetch_light_block(peer, AtHeight::At(5)).unwrap();
+
+    assert_eq!(light_block.signed_header, expected.signed_header);
+    assert_eq!(light_block.validators, expected.validators);
+    assert_eq!(light_block.next_validators, expected.next_validators);
+}
+
+#[test]
+fn prod_io_rejects_header_at_other_height() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+
+    let server = MockServer::start(light_block_responses(&tc.primary.lite_blocks[5]));
+    let peer = default_peer_id();
+    let io = server.prod_io(chain_id(&tc), peer, RetryPolicy::never());
+
+    let result = io.fetch_light_block(peer, AtHeight::At(5));
+
+    assert!(matches!(result, Err(IoError::InvalidResponse(_))));
+}
+
+#[test]
+fn prod_io_rejects_header_from_other_chain() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+
+    let server = MockServer::start(light_block_responses(&tc.primary.lite_blocks[4]));
+    let peer = default_peer_id();
+    let io = server.prod_io("other-chain".parse().unwrap(), peer, RetryPolicy::never());
+
+    let result = io.fetch_light_block(peer, AtHeight::At(5));
+
+    assert!(matches!(result, Err(IoError::InvalidResponse(_))));
+}
+
+#[test]
+fn prod_io_rejects_validator_set_not_matching_header() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let light_block = &tc.primary.lite_blocks[4];
+
+    let truncated = ValidatorSet::new(light_block.validators.validators()[..1].to_vec());
+
+    let server = MockServer::start(vec![
+        commit(&light_block.signed_header),
+        validator_set(5, &truncated),
+        validator_set(6, &light_block.next_validators),
+    ]);
+    let peer = default_peer_id();
+    let io = server.prod_io(chain_id(&tc), peer, RetryPolicy::never());
+
+    let result = io.fetch_light_block(peer, AtHeight::At(5));
+
+    assert!(matches!(result, Err(IoError::InvalidResponse(_))));
+}
+
+#[test]
+fn prod_io_rejects_validator_set_at_other_height() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let light_block = &tc.primary.lite_blocks[4];
+
+    let server = MockServer::start(vec![validator_set(6, &light_block.validators)]);
+    let peer = default_peer_id();
+    let io = server.prod_io(chain_id(&tc), peer, RetryPolicy::never());
+
+    let result = io.fetch_validator_set(peer, 5);
+
+    assert!(matches!(result, Err(IoError::InvalidResponse(_))));
+}
@@ -6,25 +6,28 @@ use std::{
     time::{Duration, SystemTime},
 };
 
-use contracts::contract_trait;
+use tendermint::{public_key::Algorithm, Hash};
 
 use tendermint_light_client::{
     components::{
-        clock::Clock,
-        io::{AtHeight, Io, IoError},
-        scheduler,
-        verifier::{ProdVerifier, Verdict, Verifier},
+        io::{AtHeight, Io},
+        scheduler::{self, Scheduler},
+        verifier::{MemoizingVerifier, ProdVerifier, Verdict, Verifier},
+    },
+    errors::{Error, ErrorKind},
+    light_client::{LightClient, Options, VerificationMode},
+    operations::{ProdCommitValidator, ProdHeaderHasher, ProdVotingPowerCalculator},
+    predicates::{
+        composed::ComposedPredicates,
+        errors::VerificationError,
+        report::{Predicate, VerificationReport},
     },
-    errors::Error,
-    light_client::{LightClient, Options},
     state::State,
     store::{memory::MemoryStore, LightStore, VerifiedStatus},
     tests::{Trusted, *},
-    types::{Height, LightBlock, PeerId, Time, TrustThreshold},
+    types::{Height, LightBlock, Time, TrustThreshold, ValidatorSet},
 };
 
-use tendermint_rpc as rpc;
-
 // Link to the commit that generated below JSON test files:
 // https://github.com/Shivani912/tendermint/commit/e02f8fd54a278f0192353e54b84a027c8fe31c1e
 const TEST_FILES_PATH: &str = "./tests/support/";
@@ -55,6 +58,7 @@ fn verify_single(
         trusting_period,
         clock_drift,
         now: now.into(),
+        verification_mode: VerificationMode::Skipping,
     };
 
     let result = verifier.verify(&input, &trusted_state, &options);
@@ -111,56 +115,6 @@ fn run_test_case(tc: TestCase<LightBlock>) {
     }
 }
 
-#[derive(Clone)]
-struct MockIo {
-    chain_id: String,
-    light_blocks: HashMap<Height, LightBlock>,
-    latest_height: Height,
-}
-
-impl MockIo {
-    fn new(chain_id: String, light_blocks: Vec<LightBlock>) -> Self {
-        let latest_height = light_blocks.iter().map(|lb| lb.height()).max().unwrap();
-
-        let light_blocks = light_blocks
-            .into_iter()
-            .map(|lb| (lb.height(), lb))
-            .collect();
-
-        Self {
-            chain_id,
-            light_blocks,
-            latest_height,
-        }
-    }
-}
-
-#[contract_trait]
-impl Io for MockIo {
-    fn fetch_light_block(&self, _peer: PeerId, height: AtHeight) -> Result<LightBlock, IoError> {
-        let height = match height {
-            AtHeight::Highest => self.latest_height,
-            AtHeight::At(height) => height,
-        };
-
-        self.light_blocks
-            .get(&height)
-            .cloned()
-            .ok_or(rpc::Error::new((-32600).into(), None).into())
-    }
-}
-
-#[derive(Clone)]
-struct MockClock {
-    now: Time,
-}
-
-impl Clock for MockClock {
-    fn now(&self) -> Time {
-        self.now
-    }
-}
-
 fn verify_bisection(
     untrusted_height: Height,
     light_client: &mut LightClient,
@@ -171,7 +125,13 @@ fn verify_bisection(
         .map(|_| state.get_trace(untrusted_height))
 }
 
-fn run_bisection_test(tc: TestBisection<LightBlock>) {
+/// Run the given bisection test case with the given scheduler,
+/// and return the number of light blocks fetched during verification.
+fn run_bisection_test(
+    tc: TestBisection<LightBlock>,
+    scheduler: impl Scheduler + 'static,
+    verification_mode: VerificationMode,
+) -> usize {
     println!("  - {}", tc.description);
 
     let primary = default_peer_id();
@@ -191,6 +151,7 @@ fn run_bisection_test(tc: TestBisection<LightBlock>) {
         trusting_period: trusting_period.into(),
         clock_drift,
         now,
+        verification_mode,
     };
 
     let expects_err = match &tc.expected_output {
@@ -207,7 +168,9 @@ fn run_bisection_test(tc: TestBisection<LightBlock>) {
         .expect("could not 'request' light block");
 
     let mut light_store = MemoryStore::new();
-    light_store.insert(trusted_state, VerifiedStatus::Verified);
+    light_store
+        .insert(trusted_state, VerifiedStatus::Verified)
+        .unwrap();
 
     let mut state = State {
         light_store: Box::new(light_store),
@@ -216,16 +179,14 @@ fn run_bisection_test(tc: TestBisection<LightBlock>) {
 
     let verifier = ProdVerifier::default();
 
-    let mut light_client = LightClient::new(
-        primary,
-        options,
-        clock,
-        scheduler::basic_bisecting_schedule,
-        verifier,
-        io.clone(),
-    );
+    let mut light_client =
+        LightClient::new(primary, options, clock, scheduler, verifier, io.clone());
+
+    let fetches_before = io.fetch_count();
+    let result = verify_bisection(untrusted_height, &mut light_client, &mut state);
+    let fetches = io.fetch_count() - fetches_before;
 
-    match verify_bisection(untrusted_height, &mut light_client, &mut state) {
+    match result {
         Ok(new_states) => {
             let untrusted_light_block = io
                 .fetch_light_block(primary.clone(), AtHeight::At(untrusted_height))
@@ -246,6 +207,8 @@ fn run_bisection_test(tc: TestBisection<LightBlock>) {
             assert!(expects_err);
         }
     }
+
+    fetches
 }
 
 fn run_single_step_tests(dir: &str) {
@@ -276,7 +239,7 @@ fn run_single_step_tests(dir: &str) {
     }
 }
 
-fn run_bisection_tests(dir: &str) {
+fn run_bisection_tests(dir: &str, verification_mode: VerificationMode) {
     let paths = fs::read_dir(PathBuf::from(TEST_FILES_PATH).join(dir)).unwrap();
 
     for file_path in paths {
@@ -289,7 +252,7 @@ fn run_bisection_tests(dir: &str) {
         );
 
         let case = read_bisection_test_case(&fp_str);
-        run_bisection_test(case);
+        run_bisection_test(case, scheduler::basic_bisecting_schedule, verification_mode);
     }
 }
 
@@ -308,7 +271,137 @@ fn read_bisection_test_case(file_path: &str) -> TestBisection<LightBlock> {
 #[test]
 fn bisection() {
     let dir = "bisection/single_peer";
-    run_bisection_tests(dir);
+    run_bisection_tests(dir, VerificationMode::Skipping);
+}
+
+#[test]
+fn bisection_sequential() {
+    let dir = "bisection/single_peer";
+    run_bisection_tests(dir, VerificationMode::Sequential);
+}
+
+#[test]
+fn sequential_verification_verifies_every_header() {
+    let tc = read_bisection_test_case(&format!(
+        "{}bisection/single_peer/happy_path.json",
+        TEST_FILES_PATH
+    ));
+
+    let primary = default_peer_id();
+    let io = MockIo::new(tc.primary.chain_id.clone(), tc.primary.lite_blocks.clone());
+
+    let options = Options {
+        trust_threshold: tc.trust_options.trust_level,
+        trusting_period: tc.trust_options.period.into(),
+        clock_drift: Duration::from_secs(10),
+        now: tc.now,
+        verification_mode: VerificationMode::Sequential,
+    };
+
+    let trusted_state = io
+        .fetch_light_block(primary, AtHeight::At(1))
+        .expect("could not 'request' light block");
+
+    let mut light_store = MemoryStore::new();
+    light_store
+        .insert(trusted_state, VerifiedStatus::Verified)
+        .unwrap();
+    let mut state = State::new(light_store);
+
+    let light_client = LightClient::new(
+        primary,
+        options,
+        MockClock { now: tc.now },
+        scheduler::basic_bisecting_schedule,
+        ProdVerifier::default(),
+        io.clone(),
+    );
+
+    let fetches_before = io.fetch_count();
+    let light_block = light_client.verify_to_target(11, &mut state).unwrap();
+
+    assert_eq!(light_block.height(), 11);
+    assert_eq!(io.fetch_count() - fetches_before, 10);
+
+    for height in 1..=11 {
+        assert!(state
+            .light_store
+            .get(height, VerifiedStatus::Verified)
+            .is_some());
+    }
+}
+
+#[test]
+fn bisection_with_store_aware_scheduler() {
+    let dir = PathBuf::from(TEST_FILES_PATH).join("bisection/single_peer");
+    let paths = fs::read_dir(dir).unwrap();
+
+    for file_path in paths {
+        let fp_str = format!("{}", file_path.unwrap().path().display());
+        let case = read_bisection_test_case(&fp_str);
+
+        let basic_fetches = run_bisection_test(
+            case.clone(),
+            scheduler::basic_bisecting_schedule,
+            VerificationMode::Skipping,
+        );
+
+        let prod_fetches = run_bisection_test(
+            case,
+            scheduler::ProdScheduler::new(),
+            VerificationMode::Skipping,
+        );
+
+        assert!(
+            prod_fetches <= basic_fetches,
+            "{}: store-aware scheduler fetched {} blocks, basic scheduler fetched {}",
+            fp_str,
+            prod_fetches,
+            basic_fetches
+        );
+    }
+}
+
+#[test]
+fn store_aware_scheduler_prunes_validator_set_changes() {
+    let tc = read_bisection_test_case(&format!(
+        "{}bisection/single_peer/happy_path.json",
+        TEST_FILES_PATH
+    ));
+
+    let block_at = |height: u64| {
+        tc.primary
+            .lite_blocks
+            .iter()
+            .find(|lb| lb.height() == height)
+            .cloned()
+            .unwrap()
+    };
+
+    // The unverified block at height 3 announces a change of the validator set.
+    let mut announcing = block_at(3);
+    announcing.signed_header.header.next_validators_hash = Hash::Sha256([0; 32]);
+
+    let mut light_store = MemoryStore::new();
+    light_store
+        .insert(block_at(1), VerifiedStatus::Verified)
+        .unwrap();
+    light_store
+        .insert(announcing, VerifiedStatus::Unverified)
+        .unwrap();
+
+    let scheduler = scheduler::ProdScheduler::new();
+
+    assert_eq!(scheduler.schedule(&light_store, 11, 11), 3);
+    assert_eq!(scheduler.validator_set_changes(), vec![3]);
+
+    // Once the light store trusts a block above the change, it is forgotten.
+    light_store
+        .insert(block_at(5), VerifiedStatus::Verified)
+        .unwrap();
+
+    assert_eq!(scheduler.schedule(&light_store, 11, 11), 8);
+    assert!(scheduler.validator_set_changes().is_empty());
 }
 
 #[test]
@@ -336,3 +429,452 @@ fn single_step_skipping() {
         run_single_step_tests(dir);
     }
 }
+
+fn backward_light_client(
+    tc: &TestBisection<LightBlock>,
+    io: MockIo,
+    now: Time,
+) -> (LightClient, State) {
+    let primary = default_peer_id();
+
+    let options = Options {
+        trust_threshold: tc.trust_options.trust_level,
+        trusting_period: tc.trust_options.period.into(),
+        clock_drift: Duration::from_secs(10),
+        now,
+        verification_mode: VerificationMode::Skipping,
+    };
+
+    let trusted_height = tc.height_to_verify.try_into().unwrap();
+    let trusted_state = io
+        .fetch_light_block(primary, AtHeight::At(trusted_height))
+        .expect("could not 'request' light block");
+
+    let mut light_store = MemoryStore::new();
+    light_store
+        .insert(trusted_state, VerifiedStatus::Verified)
+        .unwrap();
+
+    let light_client = LightClient::new(
+        primary,
+        options,
+        MockClock { now },
+        scheduler::basic_bisecting_schedule,
+        ProdVerifier::default(),
+        io,
+    );
+
+    (light_client, State::new(light_store))
+}
+
+#[test]
+fn backward_verification() {
+    let tc = read_bisection_test_case(&format!(
+        "{}bisection/single_peer/happy_path.json",
+        TEST_FILES_PATH
+    ));
+
+    let io = MockIo::new(tc.primary.chain_id.clone(), tc.primary.lite_blocks.clone());
+    let (light_client, mut state) = backward_light_client(&tc, io.clone(), tc.now);
+
+    let light_block = light_client.verify_to_target(3, &mut state).unwrap();
+
+    let expected = io
+        .fetch_light_block(default_peer_id(), AtHeight::At(3))
+        .unwrap();
+
+    assert_eq!(light_block, expected);
+
+    // Every block between the target and the trusted block is now trusted
+    for height in 3..=11 {
+        assert!(state
+            .light_store
+            .get(height, VerifiedStatus::Verified)
+            .is_some());
+    }
+
+    assert!(state.light_store.get(2, VerifiedStatus::Verified).is_none());
+}
+
+#[test]
+fn backward_verification_fails_on_broken_hash_link() {
+    let tc = read_bisection_test_case(&format!(
+        "{}bisection/single_peer/happy_path.json",
+        TEST_FILES_PATH
+    ));
+
+    let mut light_blocks = tc.primary.lite_blocks.clone();
+    let forged = light_blocks.iter_mut().find(|lb| lb.height() == 5).unwrap();
+    forged.signed_header.header.app_hash = b"forged".to_vec();
+
+    let io = MockIo::new(tc.primary.chain_id.clone(), light_blocks);
+    let (light_client, mut state) = backward_light_client(&tc, io, tc.now);
+
+    let result = light_client.verify_to_target(3, &mut state);
+
+    match result.map_err(|e| e.kind().clone()) {
+        Err(ErrorKind::InvalidLightBlock(VerificationError::InvalidHashLink { .. })) => (),
+        other => panic!("expected an invalid hash link, got: {:?}", other),
+    }
+
+    assert!(state.light_store.get(6, VerifiedStatus::Verified).is_some());
+    assert!(state.light_store.get(5, VerifiedStatus::Failed).is_some());
+    assert!(state.light_store.get(3, VerifiedStatus::Verified).is_none());
+}
+
+#[test]
+fn backward_verification_fails_on_tampered_validator_set() {
+    let tc = read_bisection_test_case(&format!(
+        "{}bisection/single_peer/happy_path.json",
+        TEST_FILES_PATH
+    ));
+
+    // The header of the block at height 5 is intact, and therefore hash-linked
+    // to the block above it, but comes with another validator set.
+    let mut light_blocks = tc.primary.lite_blocks.clone();
+    let forged = light_blocks.iter_mut().find(|lb| lb.height() == 5).unwrap();
+    forged.validators = ValidatorSet::new(forged.validators.validators()[..1].to_vec());
+
+    let io = MockIo::new(tc.primary.chain_id.clone(), light_blocks);
+    let (light_client, mut state) = backward_light_client(&tc, io, tc.now);
+
+    let result = light_client.verify_to_target(3, &mut state);
+
+    match result.map_err(|e| e.kind().clone()) {
+        Err(ErrorKind::InvalidLightBlock(VerificationError::InvalidValidatorSet { .. })) => (),
+        other => panic!("expected an invalid validator set, got: {:?}", other),
+    }
+
+    assert!(state.light_store.get(6, VerifiedStatus::Verified).is_some());
+    assert!(state.light_store.get(5, VerifiedStatus::Failed).is_some());
+    assert!(state.light_store.get(5, VerifiedStatus::Verified).is_none());
+}
+
+#[test]
+fn backward_verification_fails_outside_trusting_period() {
+    let tc = read_bisection_test_case(&format!(
+        "{}bisection/single_peer/happy_path.json",
+        TEST_FILES_PATH
+    ));
+
+    let trusting_period: Duration = tc.trust_options.period.into();
+    let now = (tc.now.to_system_time().unwrap() + trusting_period).into();
+
+    let io = MockIo::new(tc.primary.chain_id.clone(), tc.primary.lite_blocks.clone());
+    let (light_client, mut state) = backward_light_client(&tc, io, now);
+
+    let result = light_client.verify_to_target(3, &mut state);
+
+    match result.map_err(|e| e.kind().clone()) {
+        Err(ErrorKind::TrustedStateOutsideTrustingPeriod { .. }) => (),
+        other => panic!("expected an expired trusted state, got: {:?}", other),
+    }
+
+    assert!(state
+        .light_store
+        .get(10, VerifiedStatus::Verified)
+        .is_none());
+}
+
+#[test]
+fn memoizing_verifier_skips_repeated_verifications() {
+    let tc = read_bisection_test_case(&format!(
+        "{}bisection/single_peer/happy_path.json",
+        TEST_FILES_PATH
+    ));
+
+    let io = MockIo::new(tc.primary.chain_id.clone(), tc.primary.lite_blocks.clone());
+    let trusted = io
+        .fetch_light_block(default_peer_id(), AtHeight::At(1))
+        .unwrap();
+    let untrusted = io
+        .fetch_light_block(default_peer_id(), AtHeight::At(2))
+        .unwrap();
+
+    let options = Options {
+        trust_threshold: tc.trust_options.trust_level,
+        trusting_period: tc.trust_options.period.into(),
+        clock_drift: Duration::from_secs(10),
+        now: tc.now,
+        verification_mode: VerificationMode::Skipping,
+    };
+
+    let verifier = MemoizingVerifier::new(ProdVerifier::default(), 16);
+
+    for _ in 0..3 {
+        let verdict = verifier.verify(&untrusted, &trusted, &options);
+        assert!(matches!(verdict, Verdict::Success));
+    }
+
+    assert_eq!(verifier.misses(), 1);
+    assert_eq!(verifier.hits(), 2);
+
+    // A tampered light block with the same header is not served from the cache
+    let mut tampered = untrusted.clone();
+    tampered.signed_header.commit.signatures.pop();
+
+    let verdict = verifier.verify(&tampered, &trusted, &options);
+    assert!(matches!(verdict, Verdict::Invalid(_)));
+    assert_eq!(verifier.misses(), 2);
+
+    // Cached verdicts do not outlive the trusting period
+    let now = (tc.now.to_system_time().unwrap() + options.trusting_period).into();
+    let verdict = verifier.verify(&untrusted, &trusted, &options.with_now(now));
+
+    assert!(matches!(
+        verdict,
+        Verdict::Invalid(VerificationError::NotWithinTrustPeriod { .. })
+    ));
+    assert_eq!(verifier.hits(), 3);
+}
+
+#[test]
+fn explain_reports_the_outcome_of_every_predicate() {
+    let tc = read_bisection_test_case(&format!(
+        "{}bisection/single_peer/happy_path.json",
+        TEST_FILES_PATH
+    ));
+
+    let io = MockIo::new(tc.primary.chain_id.clone(), tc.primary.lite_blocks.clone());
+    let trusted = io
+        .fetch_light_block(default_peer_id(), AtHeight::At(1))
+        .unwrap();
+    let untrusted = io
+        .fetch_light_block(default_peer_id(), AtHeight::At(5))
+        .unwrap();
+
+    let options = Options {
+        trust_threshold: tc.trust_options.trust_level,
+        trusting_period: tc.trust_options.period.into(),
+        clock_drift: Duration::from_secs(10),
+        now: tc.now,
+        verification_mode: VerificationMode::Skipping,
+    };
+
+    let verifier = ProdVerifier::default();
+
+    let report = verifier.explain(&untrusted, &trusted, &options);
+
+    assert!(report.is_success());
+    assert_eq!(report.first_error(), None);
+    assert_eq!(report.untrusted_height, 5);
+
+    let evaluated: Vec<Predicate> = report
+        .predicates
+        .iter()
+        .map(|o| o.predicate.clone())
+        .collect();
+    assert!(evaluated.contains(&Predicate::HasSufficientValidatorsOverlap));
+    assert!(!evaluated.contains(&Predicate::ValidNextValidatorSet));
+
+    let overlap = report.validators_overlap.unwrap();
+    assert!(overlap.is_sufficient());
+    assert!(report.commit_power.unwrap().is_sufficient());
+    assert_eq!(
+        report.overlapping_signers.len(),
+        trusted.next_validators.validators().len()
+    );
+
+    // The report survives a round trip through JSON
+    let json = serde_json::to_string(&report).unwrap();
+    let parsed: VerificationReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, report);
+
+    // All the failing predicates are reported, not only the first one
+    let mut tampered = untrusted.clone();
+    tampered.validators = ValidatorSet::new(untrusted.validators.validators()[..1].to_vec());
+
+    let expired = (tc.now.to_system_time().unwrap() + options.trusting_period).into();
+    let options = options.with_now(expired);
+
+    let report = verifier.explain(&tampered, &trusted, &options);
+
+    let failed: Vec<Predicate> = report.failures().map(|o| o.predicate.clone()).collect();
+    assert_eq!(
+        failed,
+        vec![
+            Predicate::IsWithinTrustPeriod,
+            Predicate::ValidatorSetsMatch,
+            Predicate::ValidCommit,
+        ]
+    );
+
+    match verifier.verify(&tampered, &trusted, &options) {
+        Verdict::Invalid(e) => assert_eq!(report.first_error(), Some(&e)),
+        verdict => panic!("unexpected verdict: {:?}", verdict),
+    }
+}
+
+#[test]
+fn max_block_time_gap_bounds_the_average_gap_when_skipping() {
+    let tc = read_bisection_test_case(&format!(
+        "{}bisection/single_peer/happy_path.json",
+        TEST_FILES_PATH
+    ));
+
+    // The block at height 11 is 25s after the one at height 6, ie. 5s per block on average,
+    // and both are signed by the same validators, so that 11 is verified right from 6
+    let verify_with_max_gap = |max_gap| {
+        let io = MockIo::new(tc.primary.chain_id.clone(), tc.primary.lite_blocks.clone());
+        let trusted_state = io
+            .fetch_light_block(default_peer_id(), AtHeight::At(6))
+            .unwrap();
+
+        let mut light_store = MemoryStore::new();
+        light_store
+            .insert(trusted_state, VerifiedStatus::Verified)
+            .unwrap();
+        let mut state = State::new(light_store);
+
+        let options = Options {
+            trust_threshold: tc.trust_options.trust_level,
+            trusting_period: tc.trust_options.period.into(),
+            clock_drift: Duration::from_secs(10),
+            now: tc.now,
+            verification_mode: VerificationMode::Skipping,
+        };
+
+        let verifier = ProdVerifier::new(
+            ComposedPredicates::builder()
+                .max_block_time_gap(max_gap)
+                .build(),
+            ProdVotingPowerCalculator,
+            ProdCommitValidator,
+            ProdHeaderHasher,
+        );
+
+        let light_client = LightClient::new(
+            default_peer_id(),
+            options,
+            MockClock { now: tc.now },
+            scheduler::basic_bisecting_schedule,
+            verifier,
+            io,
+        );
+
+        light_client.verify_to_target(11, &mut state)
+    };
+
+    let light_block = verify_with_max_gap(Duration::from_secs(5)).unwrap();
+    assert_eq!(light_block.height(), 11);
+
+    match verify_with_max_gap(Duration::from_secs(4)).map_err(|e| e.kind().clone()) {
+        Err(ErrorKind::InvalidLightBlock(VerificationError::BlockTimeGapTooLarge {
+            gap,
+            max_gap,
+        })) => {
+            assert_eq!(gap, Duration::from_secs(25));
+            assert_eq!(max_gap, Duration::from_secs(20));
+        }
+        other => panic!("expected a too large block time gap, got: {:?}", other),
+    }
+}
+
+#[test]
+fn composed_predicates_enforce_local_policy() {
+    let tc = read_bisection_test_case(&format!(
+        "{}bisection/single_peer/happy_path.json",
+        TEST_FILES_PATH
+    ));
+
+    let io = MockIo::new(tc.primary.chain_id.clone(), tc.primary.lite_blocks.clone());
+    let trusted = io
+        .fetch_light_block(default_peer_id(), AtHeight::At(1))
+        .unwrap();
+    let untrusted = io
+        .fetch_light_block(default_peer_id(), AtHeight::At(2))
+        .unwrap();
+
+    let options = Options {
+        trust_threshold: tc.trust_options.trust_level,
+        trusting_period: tc.trust_options.period.into(),
+        clock_drift: Duration::from_secs(10),
+        now: tc.now,
+        verification_mode: VerificationMode::Skipping,
+    };
+
+    let verifier_with = |predicates: ComposedPredicates| {
+        ProdVerifier::new(
+            predicates,
+            ProdVotingPowerCalculator,
+            ProdCommitValidator,
+            ProdHeaderHasher,
+        )
+    };
+
+    // The fixture abides by this policy
+    let verifier = verifier_with(
+        ComposedPredicates::builder()
+            .max_block_time_gap(Duration::from_secs(3600))
+            .min_validator_count(2)
+            .allowed_key_algorithms(vec![Algorithm::Ed25519])
+            .build(),
+    );
+
+    let verdict = verifier.verify(&untrusted, &trusted, &options);
+    assert!(matches!(verdict, Verdict::Success));
+
+    let report = verifier.explain(&untrusted, &trusted, &options);
+    assert!(report.is_success());
+    assert!(report
+        .predicates
+        .iter()
+        .any(|o| o.predicate == Predicate::Extra("min_validator_count".to_string())));
+
+    // Each additional predicate fails with its own error
+    let verdict = verifier_with(
+        ComposedPredicates::builder()
+            .max_block_time_gap(Duration::from_nanos(0))
+            .build(),
+    )
+    .verify(&untrusted, &trusted, &options);
+    assert!(matches!(
+        verdict,
+        Verdict::Invalid(VerificationError::BlockTimeGapTooLarge { .. })
+    ));
+
+    let verdict = verifier_with(ComposedPredicates::builder().min_validator_count(3).build())
+        .verify(&untrusted, &trusted, &options);
+    assert!(matches!(
+        verdict,
+        Verdict::Invalid(VerificationError::InsufficientValidatorCount { count: 2, min: 3 })
+    ));
+
+    let verdict = verifier_with(
+        ComposedPredicates::builder()
+            .allowed_key_algorithms(vec![Algorithm::Secp256k1])
+            .build(),
+    )
+    .verify(&untrusted, &trusted, &options);
+    assert!(matches!(
+        verdict,
+        Verdict::Invalid(VerificationError::DisallowedValidatorKey { .. })
+    ));
+
+    let verifier = verifier_with(
+        ComposedPredicates::builder()
+            .check("even_heights_only", |untrusted, _, _| {
+                if untrusted.height() % 2 == 0 {
+                    Ok(())
+                } else {
+                    Err(VerificationError::PolicyViolation {
+                        policy: "even_heights_only".to_string(),
+                        reason: format!("odd height {}", untrusted.height()),
+                    })
+                }
+            })
+            .build(),
+    );
+    assert!(matches!(
+        verifier.verify(&untrusted, &trusted, &options),
+        Verdict::Success
+    ));
+
+    let untrusted = io
+        .fetch_light_block(default_peer_id(), AtHeight::At(3))
+        .unwrap();
+    assert!(matches!(
+        verifier.verify(&untrusted, &trusted, &options),
+        Verdict::Invalid(VerificationError::PolicyViolation { .. })
+    ));
+}
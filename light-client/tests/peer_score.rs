@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use tendermint_light_client::{
+    peer_score::{PeerEvent, PeerScore, ScoringParams},
+    types::Time,
+};
+
+fn time(rfc3339: &str) -> Time {
+    Time::parse_from_rfc3339(rfc3339).unwrap()
+}
+
+#[test]
+fn penalties_accumulate_and_decay_over_time() {
+    let params = ScoringParams {
+        half_life: Duration::from_secs(60),
+        ..ScoringParams::default()
+    };
+
+    let start = time("2020-06-01T12:00:00Z");
+    let mut score = PeerScore::new(start);
+
+    score.record(PeerEvent::Timeout, &params, start);
+    score.record(PeerEvent::InvalidBlock, &params, start);
+
+    assert_eq!(score.timeouts, 1);
+    assert_eq!(score.invalid_blocks, 1);
+
+    let penalty = params.timeout_penalty + params.invalid_block_penalty;
+    assert!((score.penalty_at(start, params.half_life) - penalty).abs() < 1e-9);
+
+    let later = time("2020-06-01T12:01:00Z");
+    assert!((score.penalty_at(later, params.half_life) - penalty / 2.0).abs() < 1e-9);
+
+    score.record(PeerEvent::Faulty, &params, later);
+
+    assert_eq!(score.faulty, 1);
+    assert!((score.penalty - (penalty / 2.0 + params.faulty_penalty)).abs() < 1e-9);
+}
+
+#[test]
+fn latency_is_a_moving_average() {
+    let params = ScoringParams::default();
+    let now = time("2020-06-01T12:00:00Z");
+    let mut score = PeerScore::new(now);
+
+    score.record(PeerEvent::Latency(Duration::from_millis(100)), &params, now);
+    assert_eq!(score.latency, Some(Duration::from_millis(100)));
+
+    score.record(PeerEvent::Latency(Duration::from_millis(600)), &params, now);
+    assert_eq!(score.latency, Some(Duration::from_millis(200)));
+
+    assert_eq!(score.penalty, 0.0);
+}
@@ -0,0 +1,141 @@
+use curve25519_dalek::{
+    constants::{ED25519_BASEPOINT_TABLE, EIGHT_TORSION},
+    edwards::CompressedEdwardsY,
+    scalar::Scalar,
+};
+use ed25519_dalek::Keypair;
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha512};
+
+use tendermint::{validator, vote, PublicKey};
+use tendermint_light_client::operations::signatures::{verify_signatures, SignatureCheck};
+
+fn signed_messages(size: usize) -> Vec<(validator::Info, Vec<u8>, Vec<u8>)> {
+    let mut csprng = OsRng;
+
+    (0..size)
+        .map(|i| {
+            let keypair = Keypair::generate(&mut csprng);
+            let sign_bytes = format!("precommit from validator #{}", i).into_bytes();
+            let signature = keypair.sign(&sign_bytes).to_bytes().to_vec();
+
+            let pub_key = PublicKey::from_raw_ed25519(keypair.public.as_bytes()).unwrap();
+            let validator = validator::Info::new(pub_key, vote::Power::new(1));
+
+            (validator, sign_bytes, signature)
+        })
+        .collect()
+}
+
+fn checks(messages: &[(validator::Info, Vec<u8>, Vec<u8>)]) -> Vec<SignatureCheck<'_>> {
+    messages
+        .iter()
+        .map(|(validator, sign_bytes, signature)| SignatureCheck {
+            validator: validator.clone(),
+            sign_bytes: sign_bytes.clone(),
+            signature,
+        })
+        .collect()
+}
+
+#[test]
+fn valid_signatures_are_accepted() {
+    let messages = signed_messages(32);
+
+    assert_eq!(verify_signatures(&checks(&messages)), Ok(()));
+    assert_eq!(verify_signatures(&[]), Ok(()));
+}
+
+#[test]
+fn invalid_signature_is_pinpointed() {
+    let mut messages = signed_messages(32);
+
+    // Swap the messages of two validators, so that their signatures do not match anymore
+    let sign_bytes = messages[20].1.clone();
+    messages[20].1 = messages[7].1.clone();
+    messages[7].1 = sign_bytes;
+
+    assert_eq!(verify_signatures(&checks(&messages)), Err(7));
+}
+
+#[test]
+fn malformed_signature_is_pinpointed() {
+    let mut messages = signed_messages(8);
+    messages[3].2.truncate(10);
+
+    assert_eq!(verify_signatures(&checks(&messages)), Err(3));
+}
+
+/// Sign the given message with a nonce commitment `R` which has a small-order component,
+/// so that the signature satisfies the cofactored verification equation checked by the
+/// Ed25519 batch verification, but not the cofactorless one checked by consensus.
+fn sign_with_torsion(keypair: &Keypair, sign_bytes: &[u8]) -> Vec<u8> {
+    let hash = Sha512::digest(keypair.secret.as_bytes());
+    let mut key = [0; 32];
+    key.copy_from_slice(&hash[..32]);
+    key[0] &= 248;
+    key[31] &= 127;
+    key[31] |= 64;
+    let secret = Scalar::from_bits(key);
+
+    let nonce = Scalar::hash_from_bytes::<Sha512>(sign_bytes);
+    let r = (&nonce * &ED25519_BASEPOINT_TABLE + EIGHT_TORSION[1])
+        .compress()
+        .to_bytes();
+
+    let mut hasher = Sha512::new();
+    hasher.input(&r);
+    hasher.input(keypair.public.as_bytes());
+    hasher.input(sign_bytes);
+    let k = Scalar::from_hash(hasher);
+
+    let s = nonce + k * secret;
+
+    let mut signature = r.to_vec();
+    signature.extend_from_slice(s.as_bytes());
+    signature
+}
+
+/// Whether the given signature satisfies the cofactored verification equation `[8](sB - R - kA) = 0`.
+fn satisfies_cofactored_equation(keypair: &Keypair, sign_bytes: &[u8], signature: &[u8]) -> bool {
+    let mut r = [0; 32];
+    r.copy_from_slice(&signature[..32]);
+    let mut s = [0; 32];
+    s.copy_from_slice(&signature[32..]);
+
+    let public_key = CompressedEdwardsY(keypair.public.to_bytes())
+        .decompress()
+        .unwrap();
+    let r_point = CompressedEdwardsY(r).decompress().unwrap();
+
+    let mut hasher = Sha512::new();
+    hasher.input(&r);
+    hasher.input(keypair.public.as_bytes());
+    hasher.input(sign_bytes);
+    let k = Scalar::from_hash(hasher);
+
+    (&Scalar::from_bits(s) * &ED25519_BASEPOINT_TABLE - r_point - k * public_key)
+        .mul_by_cofactor()
+        .is_identity()
+}
+
+#[test]
+fn signature_with_small_order_component_is_rejected() {
+    let mut messages = signed_messages(16);
+
+    let keypair = Keypair::generate(&mut OsRng);
+    let sign_bytes = b"precommit from a malicious validator".to_vec();
+    let signature = sign_with_torsion(&keypair, &sign_bytes);
+
+    assert!(satisfies_cofactored_equation(
+        &keypair,
+        &sign_bytes,
+        &signature
+    ));
+
+    let pub_key = PublicKey::from_raw_ed25519(keypair.public.as_bytes()).unwrap();
+    let validator = validator::Info::new(pub_key, vote::Power::new(1));
+    messages.insert(5, (validator, sign_bytes, signature));
+
+    assert_eq!(verify_signatures(&checks(&messages)), Err(5));
+}
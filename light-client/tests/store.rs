@@ -0,0 +1,600 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tendermint_light_client::{
+    errors::ErrorKind,
+    light_client::{Options, VerificationMode},
+    store::{
+        export::{export, import, LightStoreDump},
+        memory::MemoryStore,
+        pruning::{prune, PruningPolicy},
+        sled::{
+            migrations::{
+                self, Migration, SCHEMA_VERSION, SCHEMA_VERSION_KEY, UNVERSIONED_SCHEMA_VERSION,
+            },
+            utils::{KeyValueDb, SingleDb},
+            SledStore,
+        },
+        LightStore, VerifiedStatus,
+    },
+    tests::*,
+    types::{ChainId, Height, LightBlock, Time},
+};
+
+use common::{chain_id, read_bisection_test_case};
+
+const HAPPY_PATH: &str = "./tests/support/bisection/single_peer/happy_path.json";
+
+fn options_for(tc: &TestBisection<LightBlock>, now: Time) -> Options {
+    Options {
+        trust_threshold: tc.trust_options.trust_level,
+        trusting_period: tc.trust_options.period.into(),
+        clock_drift: Duration::from_secs(10),
+        now,
+        verification_mode: VerificationMode::Skipping,
+    }
+}
+
+/// A store with the blocks at heights 1 to 8 and 11 verified,
+/// the block at height 9 unverified, and the one at height 10 failed.
+fn populated_store(tc: &TestBisection<LightBlock>) -> MemoryStore {
+    let mut light_store = MemoryStore::new();
+
+    for light_block in &tc.primary.lite_blocks {
+        let status = match light_block.height() {
+            9 => VerifiedStatus::Unverified,
+            10 => VerifiedStatus::Failed,
+            _ => VerifiedStatus::Verified,
+        };
+
+        light_store.insert(light_block.clone(), status).unwrap();
+    }
+
+    light_store
+}
+
+fn heights(light_store: &dyn LightStore, status: VerifiedStatus) -> Vec<Height> {
+    light_store.all(status).map(|lb| lb.height()).collect()
+}
+
+#[test]
+fn prune_keeps_latest_verified_blocks() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let mut light_store = populated_store(&tc);
+
+    let policies = [PruningPolicy::KeepLatestVerified(3)];
+    let removed = prune(&mut light_store, &policies, &options_for(&tc, tc.now)).unwrap();
+
+    assert_eq!(removed, 6);
+    assert_eq!(
+        heights(&light_store, VerifiedStatus::Verified),
+        vec![7, 8, 11]
+    );
+    assert_eq!(heights(&light_store, VerifiedStatus::Unverified), vec![9]);
+    assert_eq!(heights(&light_store, VerifiedStatus::Failed), vec![10]);
+}
+
+#[test]
+fn prune_never_removes_highest_trusted_block() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let mut light_store = populated_store(&tc);
+
+    let trusting_period: Duration = tc.trust_options.period.into();
+    let now = (tc.now.to_system_time().unwrap() + trusting_period).into();
+
+    let policies = [
+        PruningPolicy::KeepLatestVerified(0),
+        PruningPolicy::OutsideTrustingPeriod,
+    ];
+
+    prune(&mut light_store, &policies, &options_for(&tc, now)).unwrap();
+
+    assert_eq!(heights(&light_store, VerifiedStatus::Verified), vec![11]);
+}
+
+#[test]
+fn prune_drops_blocks_outside_trusting_period() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let mut light_store = populated_store(&tc);
+
+    // Only the blocks more recent than the block at height 5 are still within the trusting period
+    let block_5_time = tc.primary.lite_blocks[4].signed_header.header.time;
+    let trusting_period: Duration = tc.trust_options.period.into();
+    let now = (block_5_time.to_system_time().unwrap() + trusting_period).into();
+
+    let policies = [PruningPolicy::OutsideTrustingPeriod];
+    prune(&mut light_store, &policies, &options_for(&tc, now)).unwrap();
+
+    assert_eq!(
+        heights(&light_store, VerifiedStatus::Verified),
+        vec![6, 7, 8, 11]
+    );
+    assert_eq!(heights(&light_store, VerifiedStatus::Unverified), vec![9]);
+}
+
+#[test]
+fn prune_purges_old_unverified_and_failed_blocks() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let mut light_store = populated_store(&tc);
+
+    let policies = [PruningPolicy::PurgeNotVerifiedOlderThan(
+        Duration::from_secs(60),
+    )];
+    let removed = prune(&mut light_store, &policies, &options_for(&tc, tc.now)).unwrap();
+
+    assert_eq!(removed, 2);
+    assert!(heights(&light_store, VerifiedStatus::Unverified).is_empty());
+    assert!(heights(&light_store, VerifiedStatus::Failed).is_empty());
+    assert_eq!(heights(&light_store, VerifiedStatus::Verified).len(), 9);
+}
+
+fn check_queries(light_store: &dyn LightStore, tc: &TestBisection<LightBlock>) {
+    let height = |lb: Option<LightBlock>| lb.map(|lb| lb.height());
+
+    assert_eq!(
+        height(light_store.lowest(VerifiedStatus::Verified)),
+        Some(1)
+    );
+    assert_eq!(height(light_store.lowest(VerifiedStatus::Failed)), Some(10));
+    assert_eq!(
+        height(light_store.highest(VerifiedStatus::Verified)),
+        Some(11)
+    );
+
+    let highest_below = |h, status| height(light_store.highest_below(h, status));
+    assert_eq!(highest_below(11, VerifiedStatus::Verified), Some(8));
+    assert_eq!(highest_below(5, VerifiedStatus::Verified), Some(4));
+    assert_eq!(highest_below(1, VerifiedStatus::Verified), None);
+    assert_eq!(highest_below(10, VerifiedStatus::Unverified), Some(9));
+
+    let range = |heights, status| {
+        light_store
+            .range(heights, status)
+            .map(|lb| lb.height())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        range(3..11, VerifiedStatus::Verified),
+        vec![3, 4, 5, 6, 7, 8]
+    );
+    assert_eq!(
+        range(3..12, VerifiedStatus::Verified),
+        vec![3, 4, 5, 6, 7, 8, 11]
+    );
+    assert_eq!(range(9..11, VerifiedStatus::Unverified), vec![9]);
+    assert!(range(5..5, VerifiedStatus::Verified).is_empty());
+    assert!(range(7..3, VerifiedStatus::Verified).is_empty());
+
+    let block_11_time = tc.primary.lite_blocks[10].signed_header.header.time;
+    let block_9_time = tc.primary.lite_blocks[8].signed_header.header.time;
+
+    let before = |time| height(light_store.latest_trusted_before(time));
+    assert_eq!(before(tc.now), Some(11));
+    assert_eq!(before(block_11_time), Some(8));
+    assert_eq!(before(block_9_time), Some(8));
+    assert_eq!(
+        before(tc.primary.lite_blocks[2].signed_header.header.time),
+        Some(2)
+    );
+    assert_eq!(
+        before(tc.primary.lite_blocks[0].signed_header.header.time),
+        None
+    );
+}
+
+#[test]
+fn memory_store_range_and_lowest_queries() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let light_store = populated_store(&tc);
+
+    check_queries(&light_store, &tc);
+}
+
+/// A fresh sled database at a unique path in the temporary directory,
+/// which can be reopened until it is removed on drop.
+struct TestDb {
+    path: PathBuf,
+}
+
+impl TestDb {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "light-client-store-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&path);
+
+        Self { path }
+    }
+
+    fn open(&self) -> sled::Db {
+        sled::Config::new().path(&self.path).open().unwrap()
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[test]
+fn sled_store_range_and_lowest_queries() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let test_db = TestDb::new("queries");
+    let mut light_store = SledStore::new(test_db.open(), chain_id(&tc));
+
+    for status in VerifiedStatus::iter() {
+        for light_block in populated_store(&tc).all(*status) {
+            light_store.insert(light_block, *status).unwrap();
+        }
+    }
+
+    check_queries(&light_store, &tc);
+
+    // Blocks with other statuses do not leak into the queries of a given status
+    assert_eq!(heights(&light_store, VerifiedStatus::Unverified), vec![9]);
+}
+
+fn statuses_of(light_store: &dyn LightStore, height: Height) -> Vec<VerifiedStatus> {
+    VerifiedStatus::iter()
+        .iter()
+        .copied()
+        .filter(|status| light_store.get(height, *status).is_some())
+        .collect()
+}
+
+/// Set in the child process spawned by `sled_store_update_is_atomic_across_crashes`,
+/// to the path of the database it updates before crashing.
+const CRASH_DB_PATH_VAR: &str = "LIGHT_CLIENT_CRASH_DB_PATH";
+
+#[test]
+fn sled_store_update_is_atomic_across_crashes() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+
+    if let Ok(path) = std::env::var(CRASH_DB_PATH_VAR) {
+        // In the child process: update every block, flushing only the first update,
+        // then crash without flushing the others
+        let db = sled::Config::new().path(&path).open().unwrap();
+        let mut light_store = SledStore::new(db.clone(), chain_id(&tc));
+
+        for (i, light_block) in tc.primary.lite_blocks.iter().enumerate() {
+            light_store
+                .update(light_block.clone(), VerifiedStatus::Verified)
+                .unwrap();
+
+            if i == 0 {
+                db.flush().unwrap();
+            }
+        }
+
+        std::process::abort();
+    }
+
+    let test_db = TestDb::new("crash");
+    let db = test_db.open();
+
+    let mut light_store = SledStore::new(db.clone(), chain_id(&tc));
+    for light_block in &tc.primary.lite_blocks {
+        light_store
+            .insert(light_block.clone(), VerifiedStatus::Unverified)
+            .unwrap();
+    }
+
+    db.flush().unwrap();
+    drop(light_store);
+    drop(db);
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(&["--exact", "sled_store_update_is_atomic_across_crashes"])
+        .env(CRASH_DB_PATH_VAR, &test_db.path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "the child process did not crash");
+
+    let light_store = SledStore::new(test_db.open(), chain_id(&tc));
+
+    // The flushed update made it to disk, so the crash did not just discard every update
+    let first_height = tc.primary.lite_blocks[0].height();
+    assert_eq!(
+        statuses_of(&light_store, first_height),
+        vec![VerifiedStatus::Verified]
+    );
+
+    // Each update is either entirely persisted or not at all
+    for light_block in &tc.primary.lite_blocks {
+        let statuses = statuses_of(&light_store, light_block.height());
+
+        assert!(
+            statuses == vec![VerifiedStatus::Unverified]
+                || statuses == vec![VerifiedStatus::Verified],
+            "crash left the block at height {} with statuses {:?}",
+            light_block.height(),
+            statuses
+        );
+    }
+}
+
+#[test]
+fn sled_store_update_survives_reopening() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let light_block = tc.primary.lite_blocks[4].clone();
+    let height = light_block.height();
+
+    let test_db = TestDb::new("update");
+    let db = test_db.open();
+
+    let mut light_store = SledStore::new(db.clone(), chain_id(&tc));
+    light_store
+        .insert(light_block.clone(), VerifiedStatus::Unverified)
+        .unwrap();
+    light_store
+        .update(light_block, VerifiedStatus::Verified)
+        .unwrap();
+
+    db.flush().unwrap();
+    drop(light_store);
+    drop(db);
+
+    let light_store = SledStore::new(test_db.open(), chain_id(&tc));
+
+    assert_eq!(
+        statuses_of(&light_store, height),
+        vec![VerifiedStatus::Verified]
+    );
+}
+
+#[test]
+fn sled_store_open_records_schema_version() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let test_db = TestDb::new("open-records-schema-version");
+    let db = test_db.open();
+    assert_eq!(migrations::schema_version(&db).unwrap(), None);
+
+    SledStore::open(db.clone(), chain_id(&tc)).unwrap();
+
+    assert_eq!(
+        migrations::schema_version(&db).unwrap(),
+        Some(SCHEMA_VERSION)
+    );
+}
+
+#[test]
+fn sled_store_open_upgrades_unversioned_database() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let test_db = TestDb::new("open-upgrades-unversioned-database");
+    let db = test_db.open();
+
+    // A database written before the schema version was recorded,
+    // when the light blocks were not namespaced by chain yet.
+    let legacy_db = |prefix: &str| KeyValueDb::<Height, LightBlock>::new(prefix);
+    for light_block in populated_store(&tc).all(VerifiedStatus::Verified) {
+        legacy_db("light_store/verified")
+            .insert(&db, &light_block.height(), &light_block)
+            .unwrap();
+    }
+    for light_block in populated_store(&tc).all(VerifiedStatus::Failed) {
+        legacy_db("light_store/failed")
+            .insert(&db, &light_block.height(), &light_block)
+            .unwrap();
+    }
+    assert_eq!(
+        migrations::schema_version(&db).unwrap(),
+        Some(UNVERSIONED_SCHEMA_VERSION)
+    );
+
+    let light_store = SledStore::open(db.clone(), chain_id(&tc)).unwrap();
+
+    assert_eq!(
+        migrations::schema_version(&db).unwrap(),
+        Some(SCHEMA_VERSION)
+    );
+    assert_eq!(
+        heights(&light_store, VerifiedStatus::Verified),
+        vec![1, 2, 3, 4, 5, 6, 7, 8, 11]
+    );
+    assert_eq!(heights(&light_store, VerifiedStatus::Failed), vec![10]);
+    assert_eq!(SledStore::chains(&db).unwrap(), vec![chain_id(&tc)]);
+    assert!(legacy_db("light_store/verified").iter(&db).next().is_none());
+}
+
+#[test]
+fn sled_store_open_aborts_upgrade_on_undecodable_light_block() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let test_db = TestDb::new("open-aborts-upgrade-on-undecodable-light-block");
+    let db = test_db.open();
+
+    let legacy_db = KeyValueDb::<Height, LightBlock>::new("light_store/verified");
+    legacy_db
+        .insert(&db, &1, &tc.primary.lite_blocks[0])
+        .unwrap();
+    KeyValueDb::<Height, String>::new("light_store/verified")
+        .insert(&db, &2, &"not a light block".to_string())
+        .unwrap();
+
+    assert!(SledStore::open(db.clone(), chain_id(&tc)).is_err());
+
+    // Nothing was moved, and the database is still at its previous version
+    assert_eq!(
+        migrations::schema_version(&db).unwrap(),
+        Some(UNVERSIONED_SCHEMA_VERSION)
+    );
+    assert_eq!(legacy_db.try_iter(&db).count(), 2);
+    assert!(SledStore::chains(&db).unwrap().is_empty());
+}
+
+#[test]
+fn sled_store_open_rejects_newer_schema_version() {
+    let test_db = TestDb::new("open-rejects-newer-schema-version");
+    let db = test_db.open();
+    SingleDb::new(SCHEMA_VERSION_KEY)
+        .set(&db, &(SCHEMA_VERSION + 1))
+        .unwrap();
+
+    let error = SledStore::open(db, "test-chain".parse().unwrap()).unwrap_err();
+
+    assert_eq!(
+        error.kind(),
+        &ErrorKind::UnsupportedSchemaVersion {
+            found: SCHEMA_VERSION + 1,
+            supported: SCHEMA_VERSION,
+        }
+    );
+}
+
+const MIGRATED_KEY: &str = "light_store/migrated";
+
+fn record_migration(db: &sled::Db) -> Result<(), tendermint_light_client::errors::Error> {
+    let migrated = SingleDb::<Vec<u32>>::new(MIGRATED_KEY);
+    let mut versions = migrated.get(db)?.unwrap_or_default();
+
+    versions.push(migrations::schema_version(db)?.unwrap());
+    migrated.set(db, &versions)
+}
+
+#[test]
+fn migrations_run_in_order_up_to_target_version() {
+    // A non-empty database without schema version, ie. at version 1
+    let test_db = TestDb::new("migrations-run-in-order-up-to-target-version");
+    let db = test_db.open();
+    SingleDb::new(MIGRATED_KEY)
+        .set(&db, &Vec::<u32>::new())
+        .unwrap();
+
+    let test_migrations = [
+        Migration {
+            from: 2,
+            description: "second",
+            run: record_migration,
+        },
+        Migration {
+            from: 1,
+            description: "first",
+            run: record_migration,
+        },
+    ];
+
+    let initial = migrations::migrate_to(&db, &test_migrations, 3).unwrap();
+
+    assert_eq!(initial, Some(1));
+    assert_eq!(migrations::schema_version(&db).unwrap(), Some(3));
+    assert_eq!(
+        SingleDb::<Vec<u32>>::new(MIGRATED_KEY).get(&db).unwrap(),
+        Some(vec![1, 2])
+    );
+
+    // Running the migrations again is a no-op
+    migrations::migrate_to(&db, &test_migrations, 3).unwrap();
+    assert_eq!(
+        SingleDb::<Vec<u32>>::new(MIGRATED_KEY).get(&db).unwrap(),
+        Some(vec![1, 2])
+    );
+
+    // There is no migration from version 3
+    assert!(migrations::migrate_to(&db, &test_migrations, 4).is_err());
+}
+
+#[test]
+fn export_then_import_into_sled_store() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let memory_store = populated_store(&tc);
+
+    let dump = export(&memory_store);
+    assert_eq!(dump.light_blocks.len(), 11);
+
+    let json = serde_json::to_string(&dump).unwrap();
+    let dump: LightStoreDump = serde_json::from_str(&json).unwrap();
+
+    let test_db = TestDb::new("export-then-import-into-store");
+    let mut sled_store = SledStore::open(test_db.open(), chain_id(&tc)).unwrap();
+    let imported = import(&mut sled_store, dump.clone()).unwrap();
+
+    assert_eq!(imported, 11);
+    assert_eq!(export(&sled_store), dump);
+}
+
+#[test]
+fn import_rejects_newer_dump_version() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+
+    let mut dump = export(&populated_store(&tc));
+    dump.version += 1;
+
+    let mut light_store = MemoryStore::new();
+    let result = import(&mut light_store, dump);
+
+    assert!(result.is_err());
+    assert!(light_store.all(VerifiedStatus::Verified).next().is_none());
+}
+
+/// The given light block, as if it were from the given chain.
+fn with_chain_id(light_block: &LightBlock, chain_id: &str) -> LightBlock {
+    let mut light_block = light_block.clone();
+    light_block.signed_header.header.chain_id = chain_id.parse().unwrap();
+    light_block
+}
+
+#[test]
+fn sled_database_hosts_one_light_store_per_chain() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let test_db = TestDb::new("database-hosts-one-light-store-per-chain");
+    let db = test_db.open();
+
+    // The identifier of the second chain is a prefix of the one of the fixtures
+    let other_chain: ChainId = "test-chain-0".parse().unwrap();
+    assert!(chain_id(&tc).as_str().starts_with(other_chain.as_str()));
+
+    let mut light_store = SledStore::open(db.clone(), chain_id(&tc)).unwrap();
+    let mut other_store = SledStore::open(db.clone(), other_chain).unwrap();
+
+    for light_block in &tc.primary.lite_blocks[..5] {
+        light_store
+            .insert(light_block.clone(), VerifiedStatus::Verified)
+            .unwrap();
+    }
+
+    let other_block = with_chain_id(&tc.primary.lite_blocks[2], other_chain.as_str());
+    other_store
+        .insert(other_block.clone(), VerifiedStatus::Verified)
+        .unwrap();
+
+    assert_eq!(
+        heights(&light_store, VerifiedStatus::Verified),
+        vec![1, 2, 3, 4, 5]
+    );
+    assert_eq!(heights(&other_store, VerifiedStatus::Verified), vec![3]);
+    assert_eq!(
+        other_store.get(3, VerifiedStatus::Verified),
+        Some(other_block)
+    );
+    assert_eq!(
+        SledStore::chains(&db).unwrap(),
+        vec![other_chain, chain_id(&tc)]
+    );
+}
+
+#[test]
+fn sled_store_rejects_light_blocks_from_other_chains() {
+    let tc = read_bisection_test_case(HAPPY_PATH);
+    let test_db = TestDb::new("rejects-light-blocks-from-other-chains");
+    let mut light_store = SledStore::open(test_db.open(), chain_id(&tc)).unwrap();
+
+    let light_block = with_chain_id(&tc.primary.lite_blocks[0], "other-chain");
+
+    let mismatch = ErrorKind::ChainIdMismatch {
+        expected: chain_id(&tc),
+        found: "other-chain".parse().unwrap(),
+    };
+
+    let result = light_store.insert(light_block.clone(), VerifiedStatus::Verified);
+    assert_eq!(result.unwrap_err().kind(), &mismatch);
+
+    let result = light_store.update(light_block, VerifiedStatus::Verified);
+    assert_eq!(result.unwrap_err().kind(), &mismatch);
+
+    assert!(light_store.all(VerifiedStatus::Verified).next().is_none());
+}
@@ -0,0 +1,988 @@
+mod common;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+
+use tendermint_light_client::{
+    auto_sync::{auto_sync, AutoSyncConfig, AutoSyncError},
+    components::{
+        io::{AtHeight, Io, IoError},
+        scheduler,
+        verifier::ProdVerifier,
+    },
+    errors::{self, Error, ErrorKind},
+    evidence::{Evidence, MemoryEvidenceReporter},
+    fork_detector::{Fork, ForkDetection, ForkDetector, ProdForkDetector},
+    light_client::{LightClient, Options, VerificationMode},
+    operations::ProdHeaderHasher,
+    peer_list::{HighestHeight, PeerList},
+    peer_score::PeerEvent,
+    state::State,
+    store::{
+        memory::MemoryStore, pruning::PruningPolicy, sled::SledStore, LightStore, VerifiedStatus,
+    },
+    supervisor::{Event, Instance, Supervisor},
+    tests::*,
+    types::{Height, LightBlock, PeerId, Time},
+};
+
+use common::read_bisection_test_case;
+
+const TEST_FILES_PATH: &str = "./tests/support/";
+
+fn read_multi_peer_test_case(file: &str) -> TestBisection<LightBlock> {
+    let path = PathBuf::from(TEST_FILES_PATH)
+        .join("bisection/multi_peer")
+        .join(file);
+
+    read_bisection_test_case(path)
+}
+
+fn mock_io_for(peer_id: PeerId, provider: Provider<LightBlock>) -> MockIo {
+    let light_blocks = provider
+        .lite_blocks
+        .into_iter()
+        .map(|mut lb| {
+            lb.provider = peer_id;
+            lb
+        })
+        .collect();
+
+    MockIo::new(provider.chain_id, light_blocks)
+}
+
+fn make_instance(
+    peer_id: PeerId,
+    provider: Provider<LightBlock>,
+    trusted_height: Height,
+    options: Options,
+    now: Time,
+) -> Instance {
+    let io = mock_io_for(peer_id, provider);
+    make_instance_with_io(peer_id, io, trusted_height, options, now)
+}
+
+fn make_instance_with_io(
+    peer_id: PeerId,
+    io: impl Io + 'static,
+    trusted_height: Height,
+    options: Options,
+    now: Time,
+) -> Instance {
+    let trusted_state = io
+        .fetch_light_block(peer_id, AtHeight::At(trusted_height))
+        .expect("could not 'request' light block");
+
+    let mut light_store = MemoryStore::new();
+    light_store
+        .insert(trusted_state, VerifiedStatus::Verified)
+        .unwrap();
+
+    let state = State::new(light_store);
+
+    let light_client = LightClient::new(
+        peer_id,
+        options,
+        MockClock { now },
+        scheduler::basic_bisecting_schedule,
+        ProdVerifier::default(),
+        io,
+    );
+
+    Instance::new(light_client, state)
+}
+
+fn options_for(tc: &TestBisection<LightBlock>) -> Options {
+    Options {
+        trust_threshold: tc.trust_options.trust_level,
+        trusting_period: tc.trust_options.period.into(),
+        clock_drift: Duration::from_secs(10),
+        now: tc.now,
+        verification_mode: VerificationMode::Skipping,
+    }
+}
+
+fn make_supervisor(
+    tc: TestBisection<LightBlock>,
+    reporter: MemoryEvidenceReporter,
+) -> (Vec<PeerId>, Supervisor) {
+    println!("  - {}", tc.description);
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let primary_instance = make_instance(primary, tc.primary, trusted_height, options, tc.now);
+
+    let mut peer_list = PeerList::builder().primary(primary, primary_instance);
+    let mut witnesses = Vec::with_capacity(tc.witnesses.len());
+
+    for (i, witness) in tc.witnesses.into_iter().enumerate() {
+        let peer_id: PeerId = format!("{:040X}", i + 1).parse().unwrap();
+        let instance = make_instance(peer_id, witness.value, trusted_height, options, tc.now);
+
+        peer_list = peer_list.witness(peer_id, instance);
+        witnesses.push(peer_id);
+    }
+
+    let supervisor = Supervisor::new(peer_list.build(), ProdForkDetector::default(), reporter);
+
+    (witnesses, supervisor)
+}
+
+fn run_multi_peer_test(
+    tc: TestBisection<LightBlock>,
+    reporter: MemoryEvidenceReporter,
+) -> (Vec<PeerId>, Result<LightBlock, Error>) {
+    let target_height = tc.height_to_verify.try_into().unwrap();
+    let (witnesses, mut supervisor) = make_supervisor(tc, reporter);
+
+    (witnesses, supervisor.verify_to_target(target_height))
+}
+
+fn reported_peers(reporter: &MemoryEvidenceReporter) -> HashMap<PeerId, Height> {
+    reporter
+        .reported()
+        .into_iter()
+        .map(|(peer, evidence)| match evidence {
+            Evidence::LightClientAttack(e) => (peer, e.conflicting_header.header.height.into()),
+            _ => panic!("expected light client attack evidence"),
+        })
+        .collect()
+}
+
+#[test]
+fn fork_from_the_only_witness_is_reported_to_primary() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_the_only_witness.json");
+    let reporter = MemoryEvidenceReporter::new();
+
+    let (witnesses, result) = run_multi_peer_test(tc, reporter.clone());
+
+    match result.map_err(|e| e.kind().clone()) {
+        Err(ErrorKind::ForkDetected(forked)) => assert_eq!(forked, witnesses),
+        other => panic!("expected fork to be detected, got: {:?}", other),
+    }
+
+    let reported = reported_peers(&reporter);
+    assert_eq!(reported.len(), 1);
+    assert_eq!(reported.get(&default_peer_id()), Some(&11));
+}
+
+#[test]
+fn fork_from_one_of_the_witnesses_is_reported_to_primary_and_honest_witness() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+    let reporter = MemoryEvidenceReporter::new();
+
+    let (witnesses, result) = run_multi_peer_test(tc, reporter.clone());
+
+    let forked = match result.map_err(|e| e.kind().clone()) {
+        Err(ErrorKind::ForkDetected(forked)) => forked,
+        other => panic!("expected fork to be detected, got: {:?}", other),
+    };
+
+    assert_eq!(forked.len(), 1);
+
+    let honest = witnesses.iter().find(|w| !forked.contains(w)).unwrap();
+
+    let reported = reported_peers(&reporter);
+    assert_eq!(reported.len(), 2);
+    assert!(reported.contains_key(&default_peer_id()));
+    assert!(reported.contains_key(honest));
+    assert!(!reported.contains_key(&forked[0]));
+}
+
+#[test]
+fn fork_detection_finds_divergence_height_and_traces() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let target_height: Height = tc.height_to_verify.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let mut primary_instance = make_instance(primary, tc.primary, trusted_height, options, tc.now);
+
+    let trusted_state = primary_instance
+        .state
+        .light_store
+        .highest(VerifiedStatus::Verified)
+        .unwrap();
+
+    let light_block = primary_instance
+        .light_client
+        .verify_to_target(target_height, &mut primary_instance.state)
+        .unwrap();
+
+    let witnesses: Vec<Instance> = tc
+        .witnesses
+        .into_iter()
+        .enumerate()
+        .map(|(i, witness)| {
+            let peer_id: PeerId = format!("{:040X}", i + 1).parse().unwrap();
+            make_instance(peer_id, witness.value, trusted_height, options, tc.now)
+        })
+        .collect();
+
+    let mut rt = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .build()
+        .unwrap();
+
+    let detection = rt
+        .block_on(ProdForkDetector::default().detect_forks(
+            &light_block,
+            &trusted_state,
+            &primary_instance,
+            witnesses.iter().collect(),
+        ))
+        .unwrap();
+
+    let forks = match detection {
+        ForkDetection::Detected(forks) => forks,
+        ForkDetection::NotDetected => panic!("expected fork to be detected"),
+    };
+
+    assert_eq!(forks.len(), 1);
+
+    match &forks[0] {
+        Fork::Forked(block, divergence) => {
+            assert_eq!(block.provider, witnesses[0].light_client.peer);
+            assert_eq!(divergence.height, 10);
+
+            let primary_top = divergence.primary_trace.first().unwrap();
+            let witness_top = divergence.witness_trace.first().unwrap();
+
+            assert_eq!(primary_top.height(), target_height);
+            assert_eq!(witness_top.height(), target_height);
+            assert_ne!(primary_top.signed_header, witness_top.signed_header);
+        }
+        other => panic!("expected a confirmed fork, got: {:?}", other),
+    }
+}
+
+#[test]
+fn slow_witness_does_not_stall_fork_detection() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let target_height: Height = tc.height_to_verify.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let primary_instance = make_instance(primary, tc.primary, trusted_height, options, tc.now);
+
+    // The second witness of this fixture agrees with the primary.
+    let honest_provider = tc.witnesses[1].value.clone();
+
+    let honest: PeerId = format!("{:040X}", 1).parse().unwrap();
+    let honest_instance = make_instance(
+        honest,
+        honest_provider.clone(),
+        trusted_height,
+        options,
+        tc.now,
+    );
+
+    // The slow witness answers well after the timeout of the fork detector, but not much
+    // later, as the blocking thread it holds is joined when the runtime is dropped.
+    let timeout = Duration::from_millis(500);
+
+    let slow: PeerId = format!("{:040X}", 2).parse().unwrap();
+    let slow_io = mock_io_for(slow, honest_provider);
+    let slow_fetch = move |peer: PeerId, height: AtHeight| -> Result<LightBlock, IoError> {
+        if let AtHeight::At(h) = height {
+            if h == target_height {
+                std::thread::sleep(timeout * 2);
+            }
+        }
+
+        slow_io.fetch_light_block(peer, height)
+    };
+    let slow_instance = make_instance_with_io(slow, slow_fetch, trusted_height, options, tc.now);
+
+    let peer_list = PeerList::builder()
+        .primary(primary, primary_instance)
+        .witness(honest, honest_instance)
+        .witness(slow, slow_instance)
+        .build();
+
+    let fork_detector = ProdForkDetector::new(ProdHeaderHasher, Some(timeout));
+    let mut supervisor = Supervisor::new(peer_list, fork_detector, MemoryEvidenceReporter::new());
+
+    let start = Instant::now();
+    let result = supervisor.verify_to_target(target_height);
+
+    // Verification completes after a single round of fork detection, and a single
+    // timeout is not enough for the slow witness to be put in quarantine.
+    assert!(start.elapsed() < timeout * 2);
+    assert_eq!(result.unwrap().height(), target_height);
+    assert!(supervisor.peers().witnesses.contains(&slow));
+    assert!(supervisor.peers().quarantined.is_empty());
+}
+
+#[test]
+fn unreachable_witness_is_not_deemed_faulty() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height: Height = tc.trust_options.height.try_into().unwrap();
+    let target_height: Height = tc.height_to_verify.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let mut primary_instance = make_instance(primary, tc.primary, trusted_height, options, tc.now);
+
+    let trusted_state = primary_instance
+        .state
+        .light_store
+        .highest(VerifiedStatus::Verified)
+        .unwrap();
+
+    let light_block = primary_instance
+        .light_client
+        .verify_to_target(target_height, &mut primary_instance.state)
+        .unwrap();
+
+    // The first witness of this fixture conflicts with the primary, but becomes
+    // unreachable once it served its conflicting block.
+    let witness: PeerId = format!("{:040X}", 1).parse().unwrap();
+    let witness_io = mock_io_for(witness, tc.witnesses[0].value.clone());
+    let flaky_fetch = move |peer: PeerId, height: AtHeight| -> Result<LightBlock, IoError> {
+        match height {
+            AtHeight::At(h) if h == trusted_height || h == target_height => {
+                witness_io.fetch_light_block(peer, height)
+            }
+            _ => Err(IoError::Transport(tendermint_rpc::Error::http_error(
+                "connection refused",
+            ))),
+        }
+    };
+    let witness_instance =
+        make_instance_with_io(witness, flaky_fetch, trusted_height, options, tc.now);
+
+    let mut rt = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .build()
+        .unwrap();
+
+    let detection = rt
+        .block_on(ProdForkDetector::default().detect_forks(
+            &light_block,
+            &trusted_state,
+            &primary_instance,
+            vec![&witness_instance],
+        ))
+        .unwrap();
+
+    match detection {
+        ForkDetection::Detected(forks) => match forks.as_slice() {
+            [Fork::Timeout(peer, ErrorKind::Io(IoError::Transport(_)))] => {
+                assert_eq!(*peer, witness)
+            }
+            other => panic!("expected the witness to be unreachable, got: {:?}", other),
+        },
+        ForkDetection::NotDetected => panic!("expected the witness to be reported"),
+    }
+}
+
+#[test]
+fn backward_verification_skips_fork_detection() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    // The first witness of this fixture conflicts with the primary at the two highest heights.
+    let trusted_height: Height = tc.height_to_verify.try_into().unwrap();
+    let target_height = trusted_height - 1;
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let primary_instance = make_instance(primary, tc.primary, trusted_height, options, tc.now);
+
+    let witness: PeerId = format!("{:040X}", 1).parse().unwrap();
+    let witness_instance = make_instance(
+        witness,
+        tc.witnesses[0].value.clone(),
+        trusted_height,
+        options,
+        tc.now,
+    );
+
+    let peer_list = PeerList::builder()
+        .primary(primary, primary_instance)
+        .witness(witness, witness_instance)
+        .build();
+
+    let reporter = MemoryEvidenceReporter::new();
+    let mut supervisor = Supervisor::new(peer_list, ProdForkDetector::default(), reporter.clone());
+
+    // The target is hash-linked to the trusted state of the primary,
+    // so the conflicting block of the witness is not looked at.
+    let result = supervisor.verify_to_target(target_height);
+
+    assert_eq!(result.unwrap().height(), target_height);
+    assert!(reporter.reported().is_empty());
+    assert_eq!(supervisor.peers().witnesses, vec![witness]);
+}
+
+#[test]
+fn async_handle_within_runtime() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_the_only_witness.json");
+    let target_height: Height = tc.height_to_verify.try_into().unwrap();
+
+    let (witnesses, mut supervisor) = make_supervisor(tc, MemoryEvidenceReporter::new());
+    let mut handle = supervisor.async_handle();
+
+    let mut rt = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .build()
+        .unwrap();
+
+    let result = rt.block_on(async move {
+        tokio::spawn(supervisor.run_async());
+
+        let result = handle.verify_to_target(target_height).await;
+        handle.terminate().await;
+
+        result
+    });
+
+    match result.map_err(|e| e.kind().clone()) {
+        Err(ErrorKind::ForkDetected(forked)) => assert_eq!(forked, witnesses),
+        other => panic!("expected fork to be detected, got: {:?}", other),
+    }
+}
+
+#[test]
+fn handle_notifies_subscribers_and_answers_queries() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let target_height: Height = tc.height_to_verify.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let primary_instance = make_instance(primary, tc.primary, trusted_height, options, tc.now);
+
+    // The second witness of this fixture agrees with the primary.
+    let witness: PeerId = format!("{:040X}", 1).parse().unwrap();
+    let witness_provider = tc.witnesses[1].value.clone();
+    let witness_instance =
+        make_instance(witness, witness_provider, trusted_height, options, tc.now);
+
+    let peer_list = PeerList::builder()
+        .primary(primary, primary_instance)
+        .witness(witness, witness_instance)
+        .build();
+
+    let mut supervisor = Supervisor::new(
+        peer_list,
+        ProdForkDetector::default(),
+        MemoryEvidenceReporter::new(),
+    );
+
+    let mut handle = supervisor.handle();
+    let events = handle.subscribe();
+
+    std::thread::spawn(|| supervisor.run());
+
+    let light_block = handle.verify_to_target(target_height).unwrap();
+    assert_eq!(light_block.height(), target_height);
+
+    let latest_trusted = handle.latest_trusted().unwrap();
+    assert_eq!(latest_trusted.height(), target_height);
+
+    let peers = handle.peers();
+    assert_eq!(peers.primary, primary);
+    assert_eq!(peers.witnesses, vec![witness]);
+
+    let trace = handle.verification_trace(target_height);
+    assert_eq!(trace.first().map(|lb| lb.height()), Some(target_height));
+
+    handle.terminate();
+
+    let events: Vec<Event> = futures::executor::block_on_stream(events).collect();
+
+    match events.as_slice() {
+        [Event::VerificationSuccessed(lb), Event::NewTrustedBlock(new)] => {
+            assert_eq!(lb.height(), target_height);
+            assert_eq!(new.height(), target_height);
+        }
+        other => panic!(
+            "expected a single successful verification, got: {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn errors_sent_to_subscribers_keep_their_sources() {
+    let source = std::io::Error::new(std::io::ErrorKind::Other, "disk full");
+    let error: Error = ErrorKind::Store.context(source).into();
+
+    let duplicate = errors::duplicate(&error);
+
+    assert_eq!(duplicate.kind(), error.kind());
+    assert_eq!(
+        std::error::Error::source(&duplicate).map(|source| source.to_string()),
+        Some("disk full".to_string())
+    );
+}
+
+#[test]
+fn primary_is_selected_by_policy_and_quarantined_peers_are_released() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let target_height: Height = tc.height_to_verify.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let primary_instance =
+        make_instance(primary, tc.primary.clone(), trusted_height, options, tc.now);
+
+    let lagging: PeerId = format!("{:040X}", 1).parse().unwrap();
+    let lagging_instance =
+        make_instance(lagging, tc.primary.clone(), trusted_height, options, tc.now);
+
+    // This witness has reported a higher block than the other one.
+    let synced: PeerId = format!("{:040X}", 2).parse().unwrap();
+    let mut synced_instance = make_instance(synced, tc.primary, trusted_height, options, tc.now);
+    synced_instance
+        .light_client
+        .verify_to_target(target_height, &mut synced_instance.state)
+        .unwrap();
+
+    let mut peer_list = PeerList::builder()
+        .primary(primary, primary_instance)
+        .witness(lagging, lagging_instance)
+        .witness(synced, synced_instance)
+        .primary_selector(HighestHeight)
+        .quarantine_period(Duration::from_secs(0))
+        .build();
+
+    peer_list.swap_primary().unwrap();
+    assert_eq!(peer_list.primary_id(), synced);
+
+    peer_list.quarantine_witness(&primary);
+    assert_eq!(peer_list.quarantined_ids(), vec![primary]);
+    assert!(!peer_list.witnesses_ids().contains(&primary));
+
+    assert_eq!(peer_list.release_quarantined(), vec![primary]);
+    assert!(peer_list.quarantined_ids().is_empty());
+    assert!(peer_list.witnesses_ids().contains(&primary));
+}
+
+#[test]
+fn peers_can_be_added_and_removed_via_handle() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let primary_instance =
+        make_instance(primary, tc.primary.clone(), trusted_height, options, tc.now);
+
+    let peer_list = PeerList::builder()
+        .primary(primary, primary_instance)
+        .build();
+
+    let mut supervisor = Supervisor::new(
+        peer_list,
+        ProdForkDetector::default(),
+        MemoryEvidenceReporter::new(),
+    );
+
+    let mut handle = supervisor.handle();
+    let events = handle.subscribe();
+
+    std::thread::spawn(|| supervisor.run());
+
+    let witness: PeerId = format!("{:040X}", 1).parse().unwrap();
+    let witness_instance = make_instance(witness, tc.primary, trusted_height, options, tc.now);
+
+    handle.add_witness(witness, witness_instance);
+    assert_eq!(handle.peers().witnesses, vec![witness]);
+
+    handle.remove_peer(primary).unwrap();
+
+    let peers = handle.peers();
+    assert_eq!(peers.primary, witness);
+    assert!(peers.witnesses.is_empty());
+
+    match handle.remove_peer(witness).map_err(|e| e.kind().clone()) {
+        Err(ErrorKind::NoValidPeerLeft) => (),
+        other => panic!("expected no valid peer left, got: {:?}", other),
+    }
+
+    handle.terminate();
+
+    let events: Vec<Event> = futures::executor::block_on_stream(events).collect();
+
+    match events.as_slice() {
+        [Event::PrimarySwapped(old, new)] => {
+            assert_eq!(old, &primary);
+            assert_eq!(new, &witness);
+        }
+        other => panic!("expected the primary to be swapped, got: {:?}", other),
+    }
+}
+
+#[test]
+fn peer_scores_are_persisted_in_the_light_store() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let witness: PeerId = format!("{:040X}", 1).parse().unwrap();
+
+    let db = sled::Config::new().temporary(true).open().unwrap();
+
+    let make_peer_list = || {
+        let primary_instance =
+            make_instance(primary, tc.primary.clone(), trusted_height, options, tc.now);
+
+        let mut witness_instance =
+            make_instance(witness, tc.primary.clone(), trusted_height, options, tc.now);
+        let chain_id = tc.primary.lite_blocks[0].signed_header.header.chain_id;
+        witness_instance.state.light_store = Box::new(SledStore::new(db.clone(), chain_id));
+
+        PeerList::builder()
+            .primary(primary, primary_instance)
+            .witness(witness, witness_instance)
+            .build()
+    };
+
+    let mut peer_list = make_peer_list();
+    assert!(peer_list.score(&witness).is_none());
+
+    peer_list.record(witness, PeerEvent::Timeout).unwrap();
+    peer_list.record(witness, PeerEvent::Timeout).unwrap();
+    assert!(!peer_list.should_evict(&witness));
+
+    drop(peer_list);
+
+    // Simulate a restart
+    let mut peer_list = make_peer_list();
+    assert_eq!(
+        peer_list.score(&witness).map(|score| score.timeouts),
+        Some(2)
+    );
+
+    peer_list.record(witness, PeerEvent::Timeout).unwrap();
+    assert!(peer_list.should_evict(&witness));
+}
+
+#[test]
+fn supervisor_state_is_restored_after_restart() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let target_height: Height = tc.height_to_verify.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let witnesses: Vec<PeerId> = (1..=2)
+        .map(|i| format!("{:040X}", i).parse().unwrap())
+        .collect();
+
+    let state_db = sled::Config::new().temporary(true).open().unwrap();
+    let witness_dbs: Vec<sled::Db> = witnesses
+        .iter()
+        .map(|_| sled::Config::new().temporary(true).open().unwrap())
+        .collect();
+
+    let make_supervisor = || {
+        let primary_instance =
+            make_instance(primary, tc.primary.clone(), trusted_height, options, tc.now);
+
+        let mut peer_list = PeerList::builder().primary(primary, primary_instance);
+
+        for (witness, db) in witnesses.iter().zip(&witness_dbs) {
+            let mut instance = make_instance(
+                *witness,
+                tc.primary.clone(),
+                trusted_height,
+                options,
+                tc.now,
+            );
+
+            let trusted_state = instance
+                .state
+                .light_store
+                .highest(VerifiedStatus::Verified)
+                .unwrap();
+
+            let chain_id = trusted_state.signed_header.header.chain_id;
+            let mut light_store = SledStore::new(db.clone(), chain_id);
+            light_store
+                .insert(trusted_state, VerifiedStatus::Verified)
+                .unwrap();
+            instance.state.light_store = Box::new(light_store);
+
+            peer_list = peer_list.witness(*witness, instance);
+        }
+
+        Supervisor::new(
+            peer_list.build(),
+            ProdForkDetector::default(),
+            MemoryEvidenceReporter::new(),
+        )
+    };
+
+    let mut supervisor = make_supervisor();
+    assert!(!supervisor.restore_state(&state_db).unwrap());
+    supervisor.persist_state(state_db.clone());
+
+    supervisor.remove_peer(primary).unwrap();
+    assert_eq!(supervisor.peers().primary, witnesses[0]);
+
+    let light_block = supervisor.verify_to_target(target_height).unwrap();
+    assert_eq!(light_block.height(), target_height);
+
+    let trace = supervisor.verification_trace(target_height);
+    assert!(!trace.is_empty());
+
+    drop(supervisor);
+
+    // Simulate a restart
+    let mut supervisor = make_supervisor();
+    assert!(supervisor.restore_state(&state_db).unwrap());
+
+    let peers = supervisor.peers();
+    assert_eq!(peers.primary, witnesses[0]);
+    assert_eq!(peers.witnesses, vec![witnesses[1]]);
+    assert!(peers.quarantined.is_empty());
+
+    assert_eq!(supervisor.verification_trace(target_height), trace);
+    assert_eq!(supervisor.latest_trusted(), Some(light_block));
+}
+
+#[test]
+fn peer_list_snapshot_includes_quarantined_peers() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let witness: PeerId = format!("{:040X}", 1).parse().unwrap();
+
+    let make_peer_list = || {
+        let primary_instance =
+            make_instance(primary, tc.primary.clone(), trusted_height, options, tc.now);
+        let witness_instance =
+            make_instance(witness, tc.primary.clone(), trusted_height, options, tc.now);
+
+        PeerList::builder()
+            .primary(primary, primary_instance)
+            .witness(witness, witness_instance)
+            .build()
+    };
+
+    let mut peer_list = make_peer_list();
+    peer_list.quarantine_witness(&witness);
+
+    let snapshot = peer_list.snapshot();
+    assert_eq!(snapshot.quarantined.len(), 1);
+
+    let mut restored = make_peer_list();
+    restored.restore(&snapshot).unwrap();
+
+    assert_eq!(restored.quarantined_ids(), vec![witness]);
+    assert!(restored.witnesses_ids().is_empty());
+    assert_eq!(restored.snapshot().removed, snapshot.removed);
+}
+
+#[test]
+fn auto_sync_emits_newly_trusted_blocks_until_termination() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let target_height: Height = tc.height_to_verify.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let primary_instance = make_instance(primary, tc.primary, trusted_height, options, tc.now);
+
+    // The second witness of this fixture agrees with the primary.
+    let witness: PeerId = format!("{:040X}", 1).parse().unwrap();
+    let witness_provider = tc.witnesses[1].value.clone();
+    let witness_instance =
+        make_instance(witness, witness_provider, trusted_height, options, tc.now);
+
+    let peer_list = PeerList::builder()
+        .primary(primary, primary_instance)
+        .witness(witness, witness_instance)
+        .build();
+
+    let mut supervisor = Supervisor::new(
+        peer_list,
+        ProdForkDetector::default(),
+        MemoryEvidenceReporter::new(),
+    );
+
+    let mut handle = supervisor.async_handle();
+    let sync_handle = supervisor.async_handle();
+    let mut events = handle.subscribe();
+
+    let mut rt = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let new_blocks = rt.block_on(async move {
+        tokio::spawn(supervisor.run_async());
+
+        let config = AutoSyncConfig::interval(Duration::from_millis(10));
+        let sync_task = tokio::spawn(auto_sync(sync_handle, config));
+
+        let mut new_blocks = Vec::new();
+        let mut successes = 0;
+
+        // Wait for a few rounds of synchronization
+        while successes < 3 {
+            match events.next().await.unwrap() {
+                Event::NewTrustedBlock(light_block) => new_blocks.push(light_block.height()),
+                Event::VerificationSuccessed(_) => successes += 1,
+                _ => (),
+            }
+        }
+
+        handle.terminate().await;
+
+        // The auto-sync task stops once the supervisor has terminated.
+        sync_task.await.unwrap();
+
+        new_blocks
+    });
+
+    assert_eq!(new_blocks, vec![target_height]);
+}
+
+#[test]
+fn auto_sync_records_failed_attempts() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height: Height = tc.trust_options.height.try_into().unwrap();
+    let options = options_for(&tc);
+
+    // The primary cannot serve any block but the trusted one
+    let primary = default_peer_id();
+    let primary_io = mock_io_for(primary, tc.primary);
+    let failing_fetch = move |peer: PeerId, height: AtHeight| -> Result<LightBlock, IoError> {
+        match height {
+            AtHeight::At(h) if h == trusted_height => primary_io.fetch_light_block(peer, height),
+            _ => Err(IoError::Timeout(peer)),
+        }
+    };
+    let primary_instance =
+        make_instance_with_io(primary, failing_fetch, trusted_height, options, tc.now);
+
+    let peer_list = PeerList::builder()
+        .primary(primary, primary_instance)
+        .build();
+
+    let mut supervisor = Supervisor::new(
+        peer_list,
+        ProdForkDetector::default(),
+        MemoryEvidenceReporter::new(),
+    );
+
+    let mut handle = supervisor.async_handle();
+    let sync_handle = supervisor.async_handle();
+
+    let mut config = AutoSyncConfig::interval(Duration::from_millis(10));
+    config.initial_backoff = Duration::from_millis(10);
+    let status = config.status.clone();
+
+    let mut rt = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    rt.block_on(async {
+        tokio::spawn(supervisor.run_async());
+        let sync_task = tokio::spawn(auto_sync(sync_handle, config));
+
+        while status.consecutive_failures() < 2 {
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+        }
+
+        handle.terminate().await;
+        sync_task.await.unwrap();
+    });
+
+    match status.last_error().as_deref() {
+        Some(AutoSyncError::Verification(e)) => {
+            assert_eq!(e.kind(), &ErrorKind::NoValidPeerLeft)
+        }
+        other => panic!("expected a verification failure, got: {:?}", other),
+    }
+}
+
+#[test]
+fn supervisor_prunes_light_stores_periodically() {
+    let tc = read_multi_peer_test_case("conflicting_valid_commits_from_one_of_the_witnesses.json");
+
+    let trusted_height = tc.trust_options.height.try_into().unwrap();
+    let target_height: Height = tc.height_to_verify.try_into().unwrap();
+    let options = options_for(&tc);
+
+    let primary = default_peer_id();
+    let primary_instance = make_instance(primary, tc.primary, trusted_height, options, tc.now);
+
+    // The second witness of this fixture agrees with the primary.
+    let witness: PeerId = format!("{:040X}", 1).parse().unwrap();
+    let witness_provider = tc.witnesses[1].value.clone();
+    let witness_instance =
+        make_instance(witness, witness_provider, trusted_height, options, tc.now);
+
+    let peer_list = PeerList::builder()
+        .primary(primary, primary_instance)
+        .witness(witness, witness_instance)
+        .build();
+
+    let mut supervisor = Supervisor::new(
+        peer_list,
+        ProdForkDetector::default(),
+        MemoryEvidenceReporter::new(),
+    );
+
+    supervisor.prune_periodically(
+        vec![PruningPolicy::KeepLatestVerified(1)],
+        Duration::from_millis(10),
+    );
+
+    let mut handle = supervisor.handle();
+    let events = handle.subscribe();
+
+    std::thread::spawn(|| supervisor.run());
+
+    handle.verify_to_target(target_height).unwrap();
+
+    let pruned = futures::executor::block_on_stream(events).find_map(|event| match event {
+        Event::Pruned(removed) if removed > 0 => Some(removed),
+        _ => None,
+    });
+
+    assert!(pruned.is_some());
+
+    // The highest trusted block is never pruned
+    let latest_trusted = handle.latest_trusted().unwrap();
+    assert_eq!(latest_trusted.height(), target_height);
+
+    let trace = handle.verification_trace(target_height);
+    assert_eq!(
+        trace.iter().map(|lb| lb.height()).collect::<Vec<_>>(),
+        vec![target_height]
+    );
+
+    handle.terminate();
+}
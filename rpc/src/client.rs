@@ -1,28 +1,38 @@
 //! Tendermint RPC client
 
 use bytes::buf::ext::BufExt;
-use hyper::header;
+use hyper::{client::HttpConnector, header};
 
 use tendermint::abci::{self, Transaction};
 use tendermint::block::Height;
+use tendermint::evidence::Evidence;
 use tendermint::net;
-use tendermint::Genesis;
+use tendermint::{Genesis, Hash};
 
-use crate::{endpoint::*, Error, Request, Response};
+use crate::{endpoint::*, error::Code, Error, Request, Response};
 
 /// Tendermint RPC client.
 ///
 /// Presently supports JSONRPC via HTTP.
+///
+/// The underlying HTTP connections are kept alive and re-used across requests,
+/// including by the clones of a client.
 #[derive(Clone, Debug)]
 pub struct Client {
     /// Address of the RPC server
     address: net::Address,
+
+    /// Pool of HTTP connections to the RPC server
+    http_client: hyper::Client<HttpConnector>,
 }
 
 impl Client {
     /// Create a new Tendermint RPC client, connecting to the given address
     pub fn new(address: net::Address) -> Self {
-        Self { address }
+        Self {
+            address,
+            http_client: hyper::Client::builder().build_http(),
+        }
     }
 
     /// `/abci_info`: get information about the ABCI application.
@@ -54,6 +64,11 @@ impl Client {
         self.perform(block::Request::default()).await
     }
 
+    /// `/block_by_hash`: get the block with the given hash.
+    pub async fn block_by_hash(&self, hash: Hash) -> Result<block_by_hash::Response, Error> {
+        self.perform(block_by_hash::Request::new(hash)).await
+    }
+
     /// `/block_results`: get ABCI results for a block at a particular height.
     pub async fn block_results<H>(&self, height: H) -> Result<block_results::Response, Error>
     where
@@ -109,6 +124,11 @@ impl Client {
         self.perform(broadcast::tx_commit::Request::new(tx)).await
     }
 
+    /// `/broadcast_evidence`: broadcast evidence of misbehavior.
+    pub async fn broadcast_evidence(&self, e: Evidence) -> Result<evidence::Response, Error> {
+        self.perform(evidence::Request::new(e)).await
+    }
+
     /// `/commit`: get block commit at a given height.
     pub async fn commit(&self, height: impl Into<Height>) -> Result<commit::Response, Error> {
         self.perform(commit::Request::new(height.into())).await
@@ -183,9 +203,18 @@ impl Client {
                     .unwrap(),
             );
         }
-        let http_client = hyper::Client::builder().build_http();
-        let response = http_client.request(request).await?;
+        let response = self.http_client.request(request).await?;
+        let status = response.status();
         let response_body = hyper::body::aggregate(response.into_body()).await?;
-        R::Response::from_reader(response_body.reader())
+
+        // JSONRPC errors may come with an error status, but a body which is
+        // not a JSONRPC response along with an error status is an HTTP error
+        R::Response::from_reader(response_body.reader()).map_err(|e| {
+            if e.code() == Code::ParseError && !status.is_success() {
+                Error::http_error(format!("unexpected HTTP status: {}", status))
+            } else {
+                e
+            }
+        })
     }
 }
@@ -3,10 +3,12 @@
 pub mod abci_info;
 pub mod abci_query;
 pub mod block;
+pub mod block_by_hash;
 pub mod block_results;
 pub mod blockchain;
 pub mod broadcast;
 pub mod commit;
+pub mod evidence;
 pub mod genesis;
 pub mod health;
 pub mod net_info;
@@ -0,0 +1,32 @@
+//! `/block_by_hash` endpoint JSONRPC wrapper
+
+use serde::{Deserialize, Serialize};
+
+use tendermint::{serializers, Hash};
+
+pub use super::block::Response;
+
+/// Get information about the block with the given hash
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Request {
+    /// Hash of the block to request.
+    #[serde(with = "serializers::bytes::base64string")]
+    hash: Vec<u8>,
+}
+
+impl Request {
+    /// Create a new request for information about the block with the given hash
+    pub fn new(hash: Hash) -> Self {
+        Self {
+            hash: hash.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl crate::Request for Request {
+    type Response = Response;
+
+    fn method(&self) -> crate::Method {
+        crate::Method::BlockByHash
+    }
+}
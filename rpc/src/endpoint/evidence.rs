@@ -0,0 +1,36 @@
+//! `/broadcast_evidence` endpoint JSONRPC wrapper
+
+use serde::{Deserialize, Serialize};
+
+use tendermint::{evidence::Evidence, Hash};
+
+/// `/broadcast_evidence`: broadcast evidence of misbehavior.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Request {
+    /// Evidence to broadcast
+    pub ev: Evidence,
+}
+
+impl Request {
+    /// Create a new evidence broadcast RPC request
+    pub fn new(ev: Evidence) -> Request {
+        Request { ev }
+    }
+}
+
+impl crate::Request for Request {
+    type Response = Response;
+
+    fn method(&self) -> crate::Method {
+        crate::Method::BroadcastEvidence
+    }
+}
+
+/// Response from an evidence broadcast request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Response {
+    /// Evidence hash
+    pub hash: Hash,
+}
+
+impl crate::Response for Response {}
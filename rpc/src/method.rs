@@ -21,6 +21,9 @@ pub enum Method {
     /// Get block info
     Block,
 
+    /// Get block info by hash
+    BlockByHash,
+
     /// Get ABCI results for a particular block
     BlockResults,
 
@@ -36,6 +39,9 @@ pub enum Method {
     /// Broadcast transaction commit
     BroadcastTxCommit,
 
+    /// Broadcast evidence
+    BroadcastEvidence,
+
     /// Get commit info for a block
     Commit,
 
@@ -65,11 +71,13 @@ impl Method {
             Method::AbciInfo => "abci_info",
             Method::AbciQuery => "abci_query",
             Method::Block => "block",
+            Method::BlockByHash => "block_by_hash",
             Method::BlockResults => "block_results",
             Method::Blockchain => "blockchain",
             Method::BroadcastTxAsync => "broadcast_tx_async",
             Method::BroadcastTxSync => "broadcast_tx_sync",
             Method::BroadcastTxCommit => "broadcast_tx_commit",
+            Method::BroadcastEvidence => "broadcast_evidence",
             Method::Commit => "commit",
             Method::Genesis => "genesis",
             Method::Health => "health",
@@ -89,11 +97,13 @@ impl FromStr for Method {
             "abci_info" => Method::AbciInfo,
             "abci_query" => Method::AbciQuery,
             "block" => Method::Block,
+            "block_by_hash" => Method::BlockByHash,
             "block_results" => Method::BlockResults,
             "blockchain" => Method::Blockchain,
             "broadcast_tx_async" => Method::BroadcastTxAsync,
             "broadcast_tx_sync" => Method::BroadcastTxSync,
             "broadcast_tx_commit" => Method::BroadcastTxCommit,
+            "broadcast_evidence" => Method::BroadcastEvidence,
             "commit" => Method::Commit,
             "genesis" => Method::Genesis,
             "health" => Method::Health,
@@ -5,7 +5,8 @@ mod endpoints {
     use tendermint::abci::Code;
     use tendermint::lite::Header;
 
-    use tendermint_rpc::{self as rpc, endpoint, Response};
+    use tendermint::hash::{Algorithm, Hash};
+    use tendermint_rpc::{self as rpc, endpoint, Request, Response};
 
     const EXAMPLE_APP: &str = "GaiaApp";
     const EXAMPLE_CHAIN: &str = "cosmoshub-2";
@@ -54,6 +55,19 @@ mod endpoints {
         assert_eq!(last_commit.unwrap().signatures.len(), 1);
     }
 
+    #[test]
+    fn block_by_hash_request() {
+        let hash = Hash::new(Algorithm::Sha256, &[0xAB; 32]).unwrap();
+        let request = endpoint::block_by_hash::Request::new(hash).into_json();
+        let json: serde_json::Value = serde_json::from_str(&request).unwrap();
+
+        assert_eq!(json["method"], "block_by_hash");
+        assert_eq!(
+            json["params"]["hash"],
+            "q6urq6urq6urq6urq6urq6urq6urq6urq6urq6urq6s="
+        );
+    }
+
     #[test]
     fn block_with_evidences() {
         let response =
@@ -64,6 +78,7 @@ mod endpoints {
         let evidence = evidence.iter().next().unwrap();
         match evidence {
             tendermint::evidence::Evidence::DuplicateVote(_) => {}
+            _ => panic!("expected duplicate vote evidence"),
         }
     }
 
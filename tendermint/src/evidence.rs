@@ -2,13 +2,13 @@
 
 use std::slice;
 use {
-    crate::{serializers, PublicKey, Vote},
+    crate::{block, serializers, validator, PublicKey, Time, Vote},
     serde::{Deserialize, Serialize},
 };
 
 /// Evidence of malfeasance by validators (i.e. signing conflicting votes).
-/// encoded using an Amino prefix. There is currently only a single type of
-/// evidence: `DuplicateVoteEvidence`.
+/// encoded using an Amino prefix. There are currently two types of
+/// evidence: `DuplicateVoteEvidence` and `LightClientAttackEvidence`.
 ///
 /// <https://github.com/tendermint/tendermint/blob/master/docs/spec/blockchain/blockchain.md#evidence>
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -17,6 +17,10 @@ pub enum Evidence {
     /// Duplicate vote evidence
     #[serde(rename = "tendermint/DuplicateVoteEvidence")]
     DuplicateVote(DuplicateVoteEvidence),
+
+    /// Light client attack evidence
+    #[serde(rename = "tendermint/LightClientAttackEvidence")]
+    LightClientAttack(Box<LightClientAttackEvidence>),
 }
 
 /// Duplicate vote evidence
@@ -30,6 +34,28 @@ pub struct DuplicateVoteEvidence {
     vote_b: Vote,
 }
 
+/// Light client attack evidence, ie. a block which conflicts with the block
+/// at the same height on the main chain, and which was signed by validators
+/// that a light client trusted at a common height.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LightClientAttackEvidence {
+    /// Header and commit of the conflicting block
+    pub conflicting_header: block::signed_header::SignedHeader,
+
+    /// Validator set of the conflicting block
+    pub conflicting_validators: validator::Set,
+
+    /// Height of the last block trusted by both chains
+    pub common_height: block::Height,
+
+    /// Total voting power of the validator set at the common height
+    #[serde(with = "serializers::from_str")]
+    pub total_voting_power: u64,
+
+    /// Time of the block at the common height
+    pub timestamp: Time,
+}
+
 /// Evidence data is a wrapper for a list of `Evidence`.
 ///
 /// <https://github.com/tendermint/tendermint/blob/master/docs/spec/blockchain/blockchain.md#evidencedata>